@@ -0,0 +1,441 @@
+//! The base floating-point types of [`Doubled`].
+//!
+//! [`DoubledBase`] collects the operations on `f32`, `f64`, `F32x<N>` and
+//! `F64x<N>` that the double-word kernels are built from. The kernels are
+//! written once here, generically over it, so code bounded by
+//! `T: DoubledBase` can use every arithmetic operation of `Doubled<T>` and
+//! run on scalars and SIMD vectors alike.
+//!
+//! The trait is not re-exported at the crate root, because its method names
+//! overlap with those of the `core::simd` traits.
+
+use crate::{
+    eft, AsDoubled, CheckOrder, Doubled, FullRange, MulAsDoubled, MulAsDoubledFullRange,
+    RecipAsDoubled, Upper,
+};
+use core::ops::{Add, BitAnd, BitOr, BitXor, Neg, Not, Sub};
+
+/// Base type of a [`Doubled`]: `f32`, `f64` and their SIMD vectors.
+///
+/// Comparisons return [`Mask`](Self::Mask), which is `bool` for scalars and a
+/// SIMD mask for vectors, so that kernels can handle special values lane-wise.
+pub trait DoubledBase: eft::EftFloat + Upper + CheckOrder {
+    /// Unsigned integer of the same width, or a vector of them.
+    type Bits: Copy
+        + BitAnd<Output = Self::Bits>
+        + BitOr<Output = Self::Bits>
+        + BitXor<Output = Self::Bits>;
+    /// Result of comparisons.
+    type Mask: Copy
+        + Not<Output = Self::Mask>
+        + BitAnd<Output = Self::Mask>
+        + BitOr<Output = Self::Mask>;
+    /// Signed integer holding binary exponents, or a vector of them.
+    type Exp: Copy + Add<Output = Self::Exp> + Sub<Output = Self::Exp> + Neg<Output = Self::Exp>;
+
+    /// Whether the arithmetic of `Doubled<Self>` uses fused multiply-adds.
+    /// Vectors use them where the target has the `fma` feature. The scalar
+    /// types always use Dekker's products, so that their results do not
+    /// depend on the target.
+    const FMA: bool;
+
+    /// `value` rounded to the format, in every lane.
+    fn splat(value: f64) -> Self;
+
+    fn to_bits(self) -> Self::Bits;
+
+    fn from_bits(bits: Self::Bits) -> Self;
+
+    fn abs(self) -> Self;
+
+    /// `1 / self`.
+    fn recip(self) -> Self;
+
+    /// The magnitude of `self` with the sign of `sign`.
+    fn copysign(self, sign: Self) -> Self;
+
+    fn simd_eq(self, other: Self) -> Self::Mask;
+
+    fn is_finite(self) -> Self::Mask;
+
+    /// Picks `true_values` where `mask` is set and `false_values` elsewhere.
+    fn select(mask: Self::Mask, true_values: Self, false_values: Self) -> Self;
+
+    /// Exponent of the leading bit of finite non-zero values, and zero for
+    /// zeros, infinities and NaN.
+    fn exponent(self) -> Self::Exp;
+
+    /// `self * 2^e` with a single rounding, for `|e|` up to three times the
+    /// exponent range.
+    fn mul_pow2(self, e: Self::Exp) -> Self;
+
+    /// `x * 2^e` rounded once, also into the subnormal range, see
+    /// [`Doubled::ldexp`].
+    fn ldexp_doubled(x: Doubled<Self>, e: Self::Exp) -> Doubled<Self>;
+
+    /// Absolute value of a double-word value, see [`Doubled::abs`].
+    ///
+    /// Flips the signs of both words where the high word is negative, which
+    /// includes `-0`.
+    #[inline]
+    fn abs_doubled(x: Doubled<Self>) -> Doubled<Self> {
+        let sign = Self::splat(-0.).to_bits();
+        Doubled::new(
+            x.0.abs(),
+            Self::from_bits(x.1.to_bits() ^ (x.0.to_bits() & sign)),
+        )
+    }
+
+    /// Square root of a double-word value, see [`Doubled::sqrt`].
+    ///
+    /// The square root of `x.0 + x.1` is refined by one Newton step in
+    /// double-word arithmetic.
+    #[cfg(feature = "std")]
+    #[inline]
+    fn sqrt_doubled(x: Doubled<Self>) -> Doubled<Self> {
+        let t = eft::EftFloat::sqrt(x.0 + x.1);
+        let r = (x + t.mul_as_doubled(t)) * t.recip_as_doubled();
+        sqrt_special(t, crate::Scale::scale(r, Self::splat(0.5)))
+    }
+}
+
+/// Result of a square root refined to `r` from the rounded root `t`. Zero,
+/// infinity and NaN are their own square roots, or give NaN, and the
+/// refinement gives NaN for them, so there the result is `t` with a zero low
+/// word of its sign.
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn sqrt_special<T: DoubledBase>(t: T, r: Doubled<T>) -> Doubled<T> {
+    let special = t.simd_eq(T::splat(0.)) | !t.is_finite();
+    Doubled::select(special, Doubled::new(t, signed_zero(t)), r)
+}
+
+/// Zero with the sign of `x`.
+#[inline]
+pub(crate) fn signed_zero<T: DoubledBase>(x: T) -> T {
+    T::splat(0.).copysign(x)
+}
+
+/// Builds a result whose high word is `hi`. Where `hi` is zero, infinite or
+/// NaN the low-word formulas give NaN, so the low word becomes a zero of the
+/// sign of `hi` instead, which keeps `hi + lo` equal to `hi`.
+#[inline]
+pub(crate) fn fix_special<T: DoubledBase>(hi: T, lo: T) -> Doubled<T> {
+    let special = hi.simd_eq(T::splat(0.)) | !hi.is_finite();
+    Doubled::new(hi, T::select(special, signed_zero(hi), lo))
+}
+
+#[inline]
+fn mul_pow2_doubled<T: DoubledBase>(x: Doubled<T>, e: T::Exp) -> Doubled<T> {
+    Doubled::new(x.0.mul_pow2(e), x.1.mul_pow2(e))
+}
+
+impl<T: DoubledBase> Doubled<T> {
+    #[inline]
+    pub fn abs(self) -> Self {
+        T::abs_doubled(self)
+    }
+
+    #[inline]
+    pub fn square(self) -> Self {
+        #[cfg(feature = "std")]
+        if T::FMA {
+            return self.square_fma();
+        }
+        let xh = self.0.upper();
+        let xl = self.0 - xh;
+        let r0 = self.0 * self.0;
+        Self::new(
+            r0,
+            xh * xh - r0 + (xh + xh) * xl + xl * xl + self.0 * (self.1 + self.1),
+        )
+    }
+
+    #[inline]
+    pub fn square_as_f(self) -> T {
+        #[cfg(feature = "std")]
+        if T::FMA {
+            return self.0.mul_add(self.0, self.0 * self.1 + self.0 * self.1);
+        }
+        let xh = self.0.upper();
+        let xl = self.0 - xh;
+        xh * self.1 + xh * self.1 + xl * xl + (xh * xl + xh * xl) + xh * xh
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn sqrt(self) -> Self {
+        T::sqrt_doubled(self)
+    }
+
+    #[inline]
+    pub fn mul_as_f(self, other: Self) -> T {
+        #[cfg(feature = "std")]
+        if T::FMA {
+            return self
+                .0
+                .mul_add(other.0, self.1.mul_add(other.0, self.0 * other.1));
+        }
+        let xh = self.0.upper();
+        let xl = self.0 - xh;
+        let yh = other.0.upper();
+        let yl = other.0 - yh;
+        self.1 * yh + xh * other.1 + xl * yl + xh * yl + xl * yh + xh * yh
+    }
+
+    #[inline]
+    pub fn recip(self) -> Self {
+        #[cfg(feature = "std")]
+        if T::FMA {
+            return self.recip_fma();
+        }
+        let t = self.0.recip();
+        let dh = self.0.upper();
+        let dl = self.0 - dh;
+        let th = t.upper();
+        let tl = t - th;
+        fix_special(
+            t,
+            t * (T::splat(1.) - dh * th - dh * tl - dl * th - dl * tl - self.1 * t),
+        )
+    }
+
+    /// Picks `true_values` where `mask` is set and `false_values` elsewhere.
+    #[inline]
+    pub fn select(mask: T::Mask, true_values: Self, false_values: Self) -> Self {
+        Self::new(
+            T::select(mask, true_values.0, false_values.0),
+            T::select(mask, true_values.1, false_values.1),
+        )
+    }
+
+    /// Replaces the values where `mask` is set.
+    #[inline]
+    pub fn set_masked(&mut self, mask: T::Mask, value: Self) {
+        *self = Self::select(mask, value, *self);
+    }
+}
+
+// FMA kernels, used where `T::FMA` is set. They are compiled regardless of
+// the `fma` target feature so that runtime-dispatched code can call them from
+// `#[target_feature]` functions.
+#[cfg(feature = "std")]
+impl<T: DoubledBase> Doubled<T> {
+    #[inline]
+    pub(crate) fn mul_fma(self, other: Self) -> Self {
+        let r0 = self.0 * other.0;
+        Self::new(
+            r0,
+            self.0.mul_add(
+                other.1,
+                self.1.mul_add(other.0, self.0.mul_add(other.0, -r0)),
+            ),
+        )
+    }
+
+    #[inline]
+    pub(crate) fn square_fma(self) -> Self {
+        let r0 = self.0 * self.0;
+        Self::new(
+            r0,
+            (self.0 + self.0).mul_add(self.1, self.0.mul_add(self.0, -r0)),
+        )
+    }
+
+    #[inline]
+    pub(crate) fn recip_fma(self) -> Self {
+        let q0 = self.0.recip();
+        fix_special(
+            q0,
+            q0 * (-self.1).mul_add(q0, (-self.0).mul_add(q0, T::splat(1.))),
+        )
+    }
+
+    #[inline]
+    pub(crate) fn mul_single_fma(self, other: T) -> Self {
+        let r0 = self.0 * other;
+        Self::new(r0, self.1.mul_add(other, self.0.mul_add(other, -r0)))
+    }
+
+    #[inline]
+    pub(crate) fn div_fma(self, other: Self) -> Self {
+        let t = other.0.recip();
+
+        let q0 = self.0 * t;
+        let u = t.mul_add(self.0, -q0);
+        let mut q1 = (-other.1).mul_add(t, (-other.0).mul_add(t, T::splat(1.)));
+        q1 = q0.mul_add(q1, self.1.mul_add(t, u));
+
+        fix_special(q0, q1)
+    }
+}
+
+impl<T: DoubledBase> core::ops::Mul for Doubled<T> {
+    type Output = Self;
+    #[inline]
+    fn mul(self, other: Self) -> Self {
+        #[cfg(feature = "std")]
+        if T::FMA {
+            return self.mul_fma(other);
+        }
+        let xh = self.0.upper();
+        let xl = self.0 - xh;
+        let yh = other.0.upper();
+        let yl = other.0 - yh;
+        let r0 = self.0 * other.0;
+        Self::new(
+            r0,
+            xh * yh - r0 + xl * yh + xh * yl + xl * yl + self.0 * other.1 + self.1 * other.0,
+        )
+    }
+}
+
+impl<T: DoubledBase> core::ops::Mul<T> for Doubled<T> {
+    type Output = Self;
+    #[inline]
+    fn mul(self, other: T) -> Self {
+        #[cfg(feature = "std")]
+        if T::FMA {
+            return self.mul_single_fma(other);
+        }
+        let xh = self.0.upper();
+        let xl = self.0 - xh;
+        let yh = other.upper();
+        let yl = other - yh;
+        let r0 = self.0 * other;
+        Self::new(
+            r0,
+            xh * yh - r0 + xl * yh + xh * yl + xl * yl + self.1 * other,
+        )
+    }
+}
+
+impl<T: DoubledBase> core::ops::Div for Doubled<T> {
+    type Output = Self;
+    #[inline]
+    fn div(self, other: Self) -> Self {
+        #[cfg(feature = "std")]
+        if T::FMA {
+            return self.div_fma(other);
+        }
+        let t = other.0.recip();
+        let dh = other.0.upper();
+        let dl = other.0 - dh;
+        let th = t.upper();
+        let tl = t - th;
+        let nhh = self.0.upper();
+        let nhl = self.0 - nhh;
+
+        let q0 = self.0 * t;
+
+        let u = -q0
+            + nhh * th
+            + nhh * tl
+            + nhl * th
+            + nhl * tl
+            + q0 * (T::splat(1.) - dh * th - dh * tl - dl * th - dl * tl);
+
+        fix_special(q0, t * (self.1 - q0 * other.1) + u)
+    }
+}
+
+impl<T: DoubledBase> CheckOrder for Doubled<T> {
+    #[inline]
+    fn check_order(self, other: Self) {
+        self.0.check_order(other.0)
+    }
+}
+
+impl<T: DoubledBase> CheckOrder<T> for Doubled<T> {
+    #[inline]
+    fn check_order(self, other: T) {
+        self.0.check_order(other)
+    }
+}
+
+impl<T: DoubledBase> CheckOrder<Doubled<T>> for T {
+    #[inline]
+    fn check_order(self, other: Doubled<T>) {
+        self.check_order(other.0)
+    }
+}
+
+impl<T: DoubledBase> AsDoubled for T {
+    #[inline]
+    fn as_doubled(self) -> Doubled<Self> {
+        Doubled::new(self, signed_zero(self))
+    }
+}
+
+impl<T: DoubledBase> MulAsDoubled for T {
+    #[inline]
+    fn mul_as_doubled(self, other: Self) -> Doubled<Self> {
+        #[cfg(feature = "std")]
+        if T::FMA {
+            return eft::two_prod_fma(self, other);
+        }
+        let xh = self.upper();
+        let xl = self - xh;
+        let yh = other.upper();
+        let yl = other - yh;
+        let r0 = self * other;
+        Doubled::new(r0, xh * yh - r0 + xl * yh + xh * yl + xl * yl)
+    }
+}
+
+impl<T: DoubledBase> RecipAsDoubled for T {
+    #[inline]
+    fn recip_as_doubled(self) -> Doubled<Self> {
+        #[cfg(feature = "std")]
+        if T::FMA {
+            let q0 = self.recip();
+            return fix_special(q0, q0 * (-self).mul_add(q0, T::splat(1.)));
+        }
+        let t = self.recip();
+        let dh = self.upper();
+        let dl = self - dh;
+        let th = t.upper();
+        let tl = t - th;
+        fix_special(
+            t,
+            t * (T::splat(1.) - dh * th - dh * tl - dl * th - dl * tl),
+        )
+    }
+}
+
+impl<T: DoubledBase> FullRange for Doubled<T> {
+    #[inline]
+    fn mul_full_range(self, other: Self) -> Self {
+        let (ex, ey) = (self.0.exponent(), other.0.exponent());
+        let r = mul_pow2_doubled(self, -ex) * mul_pow2_doubled(other, -ey);
+        T::ldexp_doubled(r, ex + ey)
+    }
+
+    #[inline]
+    fn div_full_range(self, other: Self) -> Self {
+        let (ex, ey) = (self.0.exponent(), other.0.exponent());
+        let r = mul_pow2_doubled(self, -ex) / mul_pow2_doubled(other, -ey);
+        T::ldexp_doubled(r, ex - ey)
+    }
+
+    #[inline]
+    fn recip_full_range(self) -> Self {
+        let e = self.0.exponent();
+        T::ldexp_doubled(mul_pow2_doubled(self, -e).recip(), -e)
+    }
+
+    #[inline]
+    fn square_full_range(self) -> Self {
+        let e = self.0.exponent();
+        T::ldexp_doubled(mul_pow2_doubled(self, -e).square(), e + e)
+    }
+}
+
+impl<T: DoubledBase> MulAsDoubledFullRange for T {
+    #[inline]
+    fn mul_as_doubled_full_range(self, other: Self) -> Doubled<Self> {
+        let (ex, ey) = (self.exponent(), other.exponent());
+        let r = self.mul_pow2(-ex).mul_as_doubled(other.mul_pow2(-ey));
+        T::ldexp_doubled(r, ex + ey)
+    }
+}
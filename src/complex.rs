@@ -0,0 +1,63 @@
+//! Complex double-word values, shared by the polynomial roots and the FFT.
+
+use crate::base::DoubledBase;
+use crate::{Doubled, Normalize};
+
+/// Complex double-word value `re + i im`, or a vector of them.
+#[derive(Clone, Copy)]
+pub(crate) struct C<T> {
+    pub(crate) re: Doubled<T>,
+    pub(crate) im: Doubled<T>,
+}
+
+impl C<f64> {
+    pub(crate) const ZERO: Self = Self {
+        re: Doubled::new(0., 0.),
+        im: Doubled::new(0., 0.),
+    };
+}
+
+impl<T: DoubledBase> C<T> {
+    #[inline(always)]
+    pub(crate) fn add(self, other: Self) -> Self {
+        Self {
+            re: (self.re + other.re).normalize(),
+            im: (self.im + other.im).normalize(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn sub(self, other: Self) -> Self {
+        Self {
+            re: (self.re - other.re).normalize(),
+            im: (self.im - other.im).normalize(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn mul(self, other: Self) -> Self {
+        Self {
+            re: (self.re * other.re - self.im * other.im).normalize(),
+            im: (self.re * other.im + self.im * other.re).normalize(),
+        }
+    }
+
+    /// `i self`.
+    #[inline(always)]
+    #[cfg_attr(not(feature = "simd"), allow(dead_code))]
+    pub(crate) fn mul_i(self) -> Self {
+        Self {
+            re: -self.im,
+            im: self.re,
+        }
+    }
+
+    #[inline(always)]
+    #[cfg_attr(not(feature = "simd"), allow(dead_code))]
+    pub(crate) fn neg(self) -> Self {
+        Self {
+            re: -self.re,
+            im: -self.im,
+        }
+    }
+}
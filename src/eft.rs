@@ -0,0 +1,134 @@
+//! Error-free transformations.
+//!
+//! Each function returns the rounded result of an operation together with its
+//! rounding error, so that the outputs sum to the exact result. Unless noted
+//! otherwise, the results are exact as long as no overflow or underflow occurs.
+
+use crate::Doubled;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Base types of the transformations: `f32`, `f64` and their SIMD vectors.
+pub trait EftFloat:
+    Copy
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    /// The Veltkamp splitting constant `2^s + 1`, where `s` is half the
+    /// significand width rounded up.
+    fn splitter() -> Self;
+
+    /// Computes `self * a + b` with a single rounding.
+    #[cfg(feature = "std")]
+    fn mul_add(self, a: Self, b: Self) -> Self;
+
+    /// Correctly rounded square root.
+    #[cfg(feature = "std")]
+    fn sqrt(self) -> Self;
+}
+
+/// Knuth's two-sum: `s + e == a + b`, where `s` is the rounded sum.
+///
+/// There is no precondition on the magnitudes of `a` and `b`.
+#[inline]
+pub fn two_sum<T: EftFloat>(a: T, b: T) -> Doubled<T> {
+    let s = a + b;
+    let v = s - a;
+    Doubled::new(s, (a - (s - v)) + (b - v))
+}
+
+/// Dekker's fast two-sum: `s + e == a + b`, where `s` is the rounded sum.
+///
+/// Requires `|a| >= |b|` (or `a == 0`); otherwise the error term may be wrong.
+#[inline]
+pub fn fast_two_sum<T: EftFloat>(a: T, b: T) -> Doubled<T> {
+    let s = a + b;
+    Doubled::new(s, b - (s - a))
+}
+
+/// Error-free sum of three values: `s + e0 + e1 == a + b + c`.
+///
+/// `s` approximates the sum to within a few ulps; it is not necessarily the
+/// correctly rounded one.
+#[inline]
+pub fn three_sum<T: EftFloat>(a: T, b: T, c: T) -> (T, T, T) {
+    let t = two_sum(a, b);
+    let s = two_sum(c, t.0);
+    let e = two_sum(t.1, s.1);
+    (s.0, e.0, e.1)
+}
+
+/// Veltkamp's split: `hi + lo == a`, where both halves fit in half of the
+/// significand, so that their pairwise products are exact.
+///
+/// Requires `|a * splitter|` not to overflow, i.e. `|a|` below about `2^996`
+/// for `f64` and `2^115` for `f32`.
+#[inline]
+pub fn split<T: EftFloat>(a: T) -> Doubled<T> {
+    let c = T::splitter() * a;
+    let hi = c - (c - a);
+    Doubled::new(hi, a - hi)
+}
+
+/// Dekker's product: `p + e == a * b`, where `p` is the rounded product.
+///
+/// Has the preconditions of [`split`] for both operands, and `e` is only exact
+/// if it does not underflow.
+#[inline]
+pub fn two_prod_split<T: EftFloat>(a: T, b: T) -> Doubled<T> {
+    let x = split(a);
+    let y = split(b);
+    let p = a * b;
+    Doubled::new(p, x.0 * y.0 - p + x.0 * y.1 + x.1 * y.0 + x.1 * y.1)
+}
+
+/// Product via a fused multiply-add: `p + e == a * b`, where `p` is the
+/// rounded product.
+///
+/// `e` is only exact if it does not underflow. Without hardware FMA support
+/// this falls back to a slow software routine; see [`two_prod`].
+#[cfg(feature = "std")]
+#[inline]
+pub fn two_prod_fma<T: EftFloat>(a: T, b: T) -> Doubled<T> {
+    let p = a * b;
+    Doubled::new(p, a.mul_add(b, -p))
+}
+
+/// `p + e == a * b`, using `two_prod_fma` when the `std` and `fma` features
+/// are enabled and [`two_prod_split`] otherwise.
+#[inline]
+pub fn two_prod<T: EftFloat>(a: T, b: T) -> Doubled<T> {
+    #[cfg(all(feature = "std", target_feature = "fma"))]
+    {
+        two_prod_fma(a, b)
+    }
+    #[cfg(not(all(feature = "std", target_feature = "fma")))]
+    {
+        two_prod_split(a, b)
+    }
+}
+
+/// Division with exact remainder: `a == q * b + r`, where `q` is the rounded
+/// quotient.
+///
+/// Has the preconditions of [`two_prod`] for `q` and `b`.
+#[inline]
+pub fn two_div<T: EftFloat>(a: T, b: T) -> (T, T) {
+    let q = a / b;
+    let p = two_prod(q, b);
+    (q, (a - p.0) - p.1)
+}
+
+/// Square root with exact remainder: `a == s * s + r`, where `s` is the
+/// rounded square root.
+///
+/// Requires `a >= 0` and has the preconditions of [`two_prod`] for `s`.
+#[cfg(feature = "std")]
+#[inline]
+pub fn two_sqrt<T: EftFloat>(a: T) -> (T, T) {
+    let s = a.sqrt();
+    let p = two_prod(s, s);
+    (s, (a - p.0) - p.1)
+}
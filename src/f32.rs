@@ -1,4 +1,5 @@
 use super::*;
+use crate::base::{fix_special, signed_zero};
 
 impl Upper for f32 {
     #[inline]
@@ -7,6 +8,107 @@ impl Upper for f32 {
     }
 }
 
+impl eft::EftFloat for f32 {
+    #[inline]
+    fn splitter() -> Self {
+        4097.
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        f32::mul_add(self, a, b)
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+}
+
+impl base::DoubledBase for f32 {
+    type Bits = u32;
+    type Mask = bool;
+    type Exp = i32;
+
+    const FMA: bool = false;
+
+    #[inline]
+    fn splat(value: f64) -> Self {
+        value as f32
+    }
+
+    /// The high word of `-0` is left as is.
+    #[inline]
+    fn abs_doubled(x: Doubled<Self>) -> Doubled<Self> {
+        if x.0 < 0. {
+            Doubled::new(-x.0, -x.1)
+        } else {
+            x
+        }
+    }
+
+    #[inline]
+    fn to_bits(self) -> u32 {
+        f32::to_bits(self)
+    }
+
+    #[inline]
+    fn from_bits(bits: u32) -> Self {
+        f32::from_bits(bits)
+    }
+
+    #[inline]
+    fn abs(self) -> Self {
+        fabsfk(self)
+    }
+
+    #[inline]
+    fn recip(self) -> Self {
+        1. / self
+    }
+
+    #[inline]
+    fn copysign(self, sign: Self) -> Self {
+        f32::copysign(self, sign)
+    }
+
+    #[inline]
+    fn simd_eq(self, other: Self) -> bool {
+        self == other
+    }
+
+    #[inline]
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+
+    #[inline]
+    fn select(mask: bool, true_values: Self, false_values: Self) -> Self {
+        if mask {
+            true_values
+        } else {
+            false_values
+        }
+    }
+
+    #[inline]
+    fn exponent(self) -> i32 {
+        ilogbk(self)
+    }
+
+    #[inline]
+    fn mul_pow2(self, e: i32) -> Self {
+        ldexpk(self, e)
+    }
+
+    #[inline]
+    fn ldexp_doubled(x: Doubled<Self>, e: i32) -> Doubled<Self> {
+        x.ldexp(e)
+    }
+}
+
 impl FromMask for Doubled<f32> {
     type Mask = u32;
     fn from_mask(u0: Self::Mask, u1: Self::Mask) -> Self {
@@ -25,10 +127,67 @@ fn fabsfk(x: f32) -> f32 {
     f32::from_bits(0x_7fff_ffff & x.to_bits())
 }
 
+/// `2^e` for `e` in the range of finite values, including subnormals.
+#[inline]
+fn pow2(e: i32) -> f32 {
+    if e >= -126 {
+        f32::from_bits(((e + 127) as u32) << 23)
+    } else {
+        f32::from_bits(1 << (e + 149))
+    }
+}
+
+/// Exponent of the leading bit of a finite non-zero `x`, and zero otherwise.
+#[inline]
+fn ilogbk(x: f32) -> i32 {
+    let bits = x.to_bits();
+    let e = ((bits >> 23) & 0xff) as i32;
+    if x == 0. || e == 0xff {
+        0
+    } else if e == 0 {
+        -118 - (bits & 0x007f_ffff).leading_zeros() as i32
+    } else {
+        e - 127
+    }
+}
+
+/// `x * 2^e` with a single rounding, for `|e|` up to three times the exponent
+/// range.
+///
+/// Scaling by a power of two rounds only when the product is subnormal, so the
+/// factors above one come first and, for negative `e`, the last factor takes
+/// as much of the scaling as it can. The product before it is then normal,
+/// unless the result underflows to zero anyway.
+#[inline]
+fn ldexpk(x: f32, e: i32) -> f32 {
+    let c = if e < 0 { e.max(-126) } else { e - 2 * (e / 3) };
+    let e1 = (e - c) / 2;
+    x * pow2(e1) * pow2(e - c - e1) * pow2(c)
+}
+
+/// `(t + err) * 2^e` with a single rounding, for a subnormal result and `err`
+/// at most half an ulp of `t`.
+///
+/// Rounding `t` alone is correct unless its scaled value is an exact tie
+/// between two subnormals, which `err` then breaks.
+#[inline]
+fn ldexp_sum(t: f32, err: f32, e: i32) -> f32 {
+    let r = ldexpk(t, e);
+    // Exact, as `r` is the nearest subnormal to `t` once scaled.
+    let d = t - ldexpk(r, -e);
+    let g = ldexpk(d + d, e);
+    let tie = fabsfk(g) == pow2(-149) && ldexpk(g, -e) == d + d;
+    if tie && err != 0. && (err > 0.) == (d > 0.) {
+        r + g
+    } else {
+        r
+    }
+}
+
 impl core::convert::From<f32> for Doubled<f32> {
     #[inline]
     fn from(f: f32) -> Self {
-        Self::new(f, 0.)
+        Self::new(f, signed_zero(f))
     }
 }
 
@@ -46,183 +205,196 @@ impl core::convert::From<f64> for Doubled<f32> {
     }
 }
 
-impl Doubled<f32> {
-    #[inline]
-    pub fn abs(self) -> Self {
-        if self.0 < 0. {
-            Self::new(-self.0, -self.1)
-        } else {
-            self
-        }
-    }
-
-    #[inline]
-    pub fn square(self) -> Self {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let r0 = self.0 * self.0;
-        Self::new(
-            r0,
-            xh * xh - r0 + (xh + xh) * xl + xl * xl + self.0 * (self.1 + self.1),
-        )
+/// `x.0 + x.1` rounded once to `f32`, for normalized `x`.
+///
+/// Where `x.1` is not zero, an even `x.0` is first moved one ulp toward `x.1`
+/// (rounding to odd), so that a tie of `f32` can only be hit exactly.
+#[inline]
+pub(crate) fn round_to_f32(x: Doubled<f64>) -> f32 {
+    let s = x.0 + x.1;
+    let bits = s.to_bits();
+    if x.1 == 0. || bits & 1 == 1 || !s.is_finite() {
+        s as f32
+    } else if (x.1 > 0.) == (s > 0.) {
+        f64::from_bits(bits + 1) as f32
+    } else {
+        f64::from_bits(bits - 1) as f32
     }
+}
 
+/// Rounds to the nearest `Doubled<f32>`: the high word is rounded to `f32` and
+/// the rest, computed exactly, is rounded once more.
+impl core::convert::From<Doubled<f64>> for Doubled<f32> {
     #[inline]
-    pub fn square_as_f(self) -> f32 {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        xh * self.1 + xh * self.1 + xl * xl + (xh * xl + xh * xl) + xh * xh
+    fn from(f: Doubled<f64>) -> Self {
+        let x = f.0 as f32;
+        fix_special(x, round_to_f32(eft::two_sum(f.0 - x as f64, f.1)))
     }
+}
 
+/// Exact widening. The words are renormalized in `f64`, where their sum
+/// usually fits in the high word alone.
+impl core::convert::From<Doubled<f32>> for Doubled<f64> {
     #[inline]
-    pub fn mul_as_f(self, other: Self) -> f32 {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let yh = other.0.upper();
-        let yl = other.0 - yh;
-        self.1 * yh + xh * other.1 + xl * yl + xh * yl + xl * yh + xh * yh
+    fn from(f: Doubled<f32>) -> Self {
+        let r = eft::two_sum(f.0 as f64, f.1 as f64);
+        fix_special(r.0, r.1)
     }
+}
 
+impl Doubled<f32> {
     #[inline]
     pub fn from_f64(f: f64) -> Self {
         let x = f as f32;
-        Self::new(x, (f - (x as f64)) as f32)
+        fix_special(x, (f - (x as f64)) as f32)
     }
 
-    pub fn recip(self) -> Self {
-        let t = 1. / self.0;
-        let dh = self.0.upper();
-        let dl = self.0 - dh;
-        let th = t.upper();
-        let tl = t - th;
-        Doubled::new(
-            t,
-            t * (1. - dh * th - dh * tl - dl * th - dl * tl - self.1 * t),
-        )
-    }
-}
-
-impl CheckOrder for Doubled<f32> {
+    /// The value rounded to `f64`. The sum of the widened words is rounded once,
+    /// so the result is correctly rounded.
     #[inline]
-    fn check_order(self, other: Self) {
-        self.0.check_order(other.0)
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 + self.1 as f64
     }
 }
 
-impl CheckOrder<f32> for Doubled<f32> {
-    #[inline]
-    fn check_order(self, other: f32) {
-        self.0.check_order(other)
+impl Doubled<f32> {
+    /// Spacing of the double-word format at the value of `self`.
+    ///
+    /// This is the ulp of a binary format with a 48-bit significand, but
+    /// never finer than the smallest subnormal `f32`. It is infinite for
+    /// infinities and NaN for NaN.
+    pub fn ulp(self) -> f32 {
+        if !self.0.is_finite() {
+            return fabsfk(self.0);
+        }
+        let bits = self.0.to_bits();
+        let biased = ((bits >> 23) & 0xff) as i32;
+        if biased == 0 {
+            return pow2(-149);
+        }
+        let mut e = biased - 127;
+        // Just below a power of two the value lies in the binade below.
+        if bits & 0x007f_ffff == 0 && self.1 != 0. && (self.1 < 0.) != (self.0 < 0.) {
+            e -= 1;
+        }
+        pow2((e - 47).max(-149))
     }
-}
 
-impl CheckOrder<Doubled<f32>> for f32 {
-    #[inline]
-    fn check_order(self, other: Doubled<f32>) {
-        self.check_order(other.0)
+    /// The next double-word value towards positive infinity.
+    ///
+    /// The low word is stepped by [`ulp`](Self::ulp) and the pair is normalized.
+    pub fn next_up(self) -> Self {
+        let mut u = self.ulp();
+        // From a negative power of two, the step lands in the binade below.
+        if self.0 < 0. && self.1 == 0. && self.0.to_bits() & 0x007f_ffff == 0 {
+            u = (u * 0.5).max(pow2(-149));
+        }
+        Self::new(self.0, self.1 + u).normalize()
     }
-}
 
-impl CheckOrder for f32 {
-    #[inline]
-    fn check_order(self, other: Self) {
-        debug_assert!(self.check() || other.check() || fabsfk(self) >= fabsfk(other));
+    /// The next double-word value towards negative infinity.
+    ///
+    /// The low word is stepped by [`ulp`](Self::ulp) and the pair is normalized.
+    pub fn next_down(self) -> Self {
+        -(-self).next_up()
     }
-}
 
-impl core::ops::Add<Doubled<f32>> for f32 {
-    type Output = Doubled<f32>;
-    #[inline]
-    fn add(self, other: Doubled<f32>) -> Self::Output {
-        let r0 = self + other.0;
-        let v = r0 - self; // == other.0
-        Doubled::new(r0, self - (r0 - v) + (other.0 - v) + other.1) // [other.0+self, other.1]
+    /// Distance between `self` and `other` in ulps of the one of larger magnitude.
+    pub fn ulp_distance(self, other: Self) -> f32 {
+        let d = self - other;
+        let m = self.abs() - other.abs();
+        let u = if m.0 + m.1 >= 0. {
+            self.ulp()
+        } else {
+            other.ulp()
+        };
+        fabsfk(d.0 + d.1) / u
     }
 }
 
-impl core::ops::Mul for Doubled<f32> {
-    type Output = Self;
-    #[inline]
-    fn mul(self, other: Self) -> Self {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let yh = other.0.upper();
-        let yl = other.0 - yh;
-        let r0 = self.0 * other.0;
-        Self::new(
-            r0,
-            xh * yh - r0 + xl * yh + xh * yl + xl * yl + self.0 * other.1 + self.1 * other.0,
-        )
+// Power-of-two scaling and exponent extraction.
+impl Doubled<f32> {
+    /// `self * 2^e`.
+    ///
+    /// Each word is scaled with a single rounding. When the result falls into
+    /// the subnormal range, the residual of the high word and the low word are
+    /// added exactly, so that the sum is still rounded only once.
+    pub fn ldexp(self, e: i32) -> Self {
+        let e = e.clamp(-300, 300);
+        let hi = ldexpk(self.0, e);
+        let lo = if fabsfk(hi) <= f32::MIN_POSITIVE {
+            let r = eft::two_sum(self.0 - ldexpk(hi, -e), self.1);
+            ldexp_sum(r.0, r.1, e)
+        } else {
+            ldexpk(self.1, e)
+        };
+        // A zero sum takes the sign of the high word, as `-0. * 2^e` would.
+        let s = if hi + lo == 0. { hi } else { hi + lo };
+        fix_special(s, hi - s + lo)
     }
-}
 
-impl core::ops::Mul<f32> for Doubled<f32> {
-    type Output = Self;
+    /// Same as [`ldexp`](Self::ldexp), the radix being two.
     #[inline]
-    fn mul(self, other: f32) -> Self {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let yh = other.upper();
-        let yl = other - yh;
-        let r0 = self.0 * other;
-        Self::new(
-            r0,
-            xh * yh - r0 + xl * yh + xh * yl + xl * yl + self.1 * other,
-        )
+    pub fn scalbn(self, e: i32) -> Self {
+        self.ldexp(e)
     }
-}
-
-impl core::ops::Div for Doubled<f32> {
-    type Output = Self;
-    #[inline]
-    fn div(self, other: Self) -> Self {
-        let t = 1. / other.0;
-        let dh = other.0.upper();
-        let dl = other.0 - dh;
-        let th = t.upper();
-        let tl = t - th;
-        let nhh = self.0.upper();
-        let nhl = self.0 - nhh;
-
-        let q0 = self.0 * t;
 
-        let u = -q0
-            + nhh * th
-            + nhh * tl
-            + nhl * th
-            + nhl * tl
-            + q0 * (1. - dh * th - dh * tl - dl * th - dl * tl);
+    /// Exponent of the leading bit of `self.0 + self.1`.
+    ///
+    /// Returns `i32::MIN` for zeros and NaN, and `i32::MAX` for infinities.
+    pub fn ilogb(self) -> i32 {
+        if self.0 == 0. || self.0.is_nan() {
+            return i32::MIN;
+        }
+        if self.0.is_infinite() {
+            return i32::MAX;
+        }
+        let e = ilogbk(self.0);
+        // Just below a power of two the value lies in the binade below.
+        if self.0.to_bits() & 0x007f_ffff == 0 && self.1 != 0. && (self.1 < 0.) != (self.0 < 0.) {
+            e - 1
+        } else {
+            e
+        }
+    }
 
-        Self::new(q0, t * (self.1 - q0 * other.1) + u)
+    /// Splits `self` into `m * 2^e` with `|m|` in `[0.5, 1)`.
+    ///
+    /// Zeros, infinities and NaN are returned unchanged with a zero exponent.
+    pub fn frexp(self) -> (Self, i32) {
+        if self.0 == 0. || !self.0.is_finite() {
+            return (self, 0);
+        }
+        let e = self.ilogb() + 1;
+        (self.ldexp(-e), e)
     }
 }
 
-impl AsDoubled for f32 {
+impl CheckOrder for f32 {
     #[inline]
-    fn as_doubled(self) -> Doubled<Self> {
-        Doubled::new(self, 0.)
+    fn check_order(self, other: Self) {
+        debug_assert!(self.check() || other.check() || fabsfk(self) >= fabsfk(other));
     }
 }
 
-impl MulAsDoubled for f32 {
+impl core::ops::Add<Doubled<f32>> for f32 {
+    type Output = Doubled<f32>;
     #[inline]
-    fn mul_as_doubled(self, other: Self) -> Doubled<Self> {
-        let xh = self.upper();
-        let xl = self - xh;
-        let yh = other.upper();
-        let yl = other - yh;
-        let r0 = self * other;
-        Doubled::new(r0, xh * yh - r0 + xl * yh + xh * yl + xl * yl)
+    fn add(self, other: Doubled<f32>) -> Self::Output {
+        let r0 = self + other.0;
+        let v = r0 - self; // == other.0
+        Doubled::new(r0, self - (r0 - v) + (other.0 - v) + other.1) // [other.0+self, other.1]
     }
 }
 
-impl RecipAsDoubled for f32 {
-    fn recip_as_doubled(self) -> Doubled<f32> {
-        let t = 1. / self;
-        let dh = self.upper();
-        let dl = self - dh;
-        let th = t.upper();
-        let tl = t - th;
-        Doubled::new(t, t * (1. - dh * th - dh * tl - dl * th - dl * tl))
+impl DoubledRandom for Doubled<f32> {
+    /// Uses the upper 48 bits of one word from `next`. Values within `2^-25`
+    /// of one are normalized to a high word of one and a negative low word.
+    #[inline]
+    fn random<F: FnMut() -> u64>(next: &mut F) -> Self {
+        let bits = next();
+        let hi = bits >> 40;
+        let lo = (bits >> 16) & 0xff_ffff;
+        Self::new(hi as f32 * pow2(-24), lo as f32 * pow2(-48)).normalize()
     }
 }
@@ -1,9 +1,18 @@
+#[cfg(feature = "enable_recsqrt_sp")]
+use crate::base::sqrt_special;
+use crate::base::{fix_special, signed_zero};
 use crate::*;
-use core::simd::num::SimdFloat;
-use std::simd::{Simd, StdFloat};
+use core::simd::cmp::{SimdOrd, SimdPartialEq, SimdPartialOrd};
+use core::simd::num::{SimdFloat, SimdInt, SimdUint};
+use std::simd::{Mask, Select, Simd, StdFloat};
 
 type F32x<const N: usize> = Simd<f32, N>;
+type F64x<const N: usize> = Simd<f64, N>;
+type U64x<const N: usize> = Simd<u64, N>;
 type U32x<const N: usize> = Simd<u32, N>;
+type I32x<const N: usize> = Simd<i32, N>;
+type M32x<const N: usize> = Mask<i32, N>;
+type Usizex<const N: usize> = Simd<usize, N>;
 
 impl<const N: usize> Upper for F32x<N> {
     #[inline]
@@ -12,6 +21,103 @@ impl<const N: usize> Upper for F32x<N> {
     }
 }
 
+impl<const N: usize> eft::EftFloat for F32x<N> {
+    #[inline]
+    fn splitter() -> Self {
+        Self::splat(4097.)
+    }
+
+    #[inline]
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        StdFloat::mul_add(self, a, b)
+    }
+
+    #[inline]
+    fn sqrt(self) -> Self {
+        StdFloat::sqrt(self)
+    }
+}
+
+impl<const N: usize> base::DoubledBase for F32x<N> {
+    type Bits = U32x<N>;
+    type Mask = M32x<N>;
+    type Exp = I32x<N>;
+
+    const FMA: bool = cfg!(target_feature = "fma");
+
+    #[inline]
+    fn splat(value: f64) -> Self {
+        F32x::splat(value as f32)
+    }
+
+    #[inline]
+    fn to_bits(self) -> U32x<N> {
+        SimdFloat::to_bits(self)
+    }
+
+    #[inline]
+    fn from_bits(bits: U32x<N>) -> Self {
+        SimdFloat::from_bits(bits)
+    }
+
+    #[inline]
+    fn abs(self) -> Self {
+        SimdFloat::abs(self)
+    }
+
+    #[inline]
+    fn recip(self) -> Self {
+        SimdFloat::recip(self)
+    }
+
+    #[inline]
+    fn copysign(self, sign: Self) -> Self {
+        SimdFloat::copysign(self, sign)
+    }
+
+    #[inline]
+    fn simd_eq(self, other: Self) -> M32x<N> {
+        SimdPartialEq::simd_eq(self, other)
+    }
+
+    #[inline]
+    fn is_finite(self) -> M32x<N> {
+        SimdFloat::is_finite(self)
+    }
+
+    #[inline]
+    fn select(mask: M32x<N>, true_values: Self, false_values: Self) -> Self {
+        mask.select(true_values, false_values)
+    }
+
+    #[inline]
+    fn exponent(self) -> I32x<N> {
+        ilogbk(self)
+    }
+
+    #[inline]
+    fn mul_pow2(self, e: I32x<N>) -> Self {
+        ldexpk(self, e)
+    }
+
+    #[inline]
+    fn ldexp_doubled(x: Doubled<Self>, e: I32x<N>) -> Doubled<Self> {
+        x.ldexp(e)
+    }
+
+    #[cfg(feature = "enable_recsqrt_sp")]
+    #[inline]
+    fn sqrt_doubled(x: Doubled<Self>) -> Doubled<Self> {
+        let s = StdFloat::sqrt(x.0 + x.1);
+        let t = F32x::splat(1.) / s;
+        let r = x * t;
+        sqrt_special(
+            s,
+            (r * (r * t + F32x::splat(-3.0))).scale(F32x::splat(-0.5)),
+        )
+    }
+}
+
 impl<const N: usize> FromMask for Doubled<F32x<N>> {
     type Mask = U32x<N>;
     fn from_mask(u0: Self::Mask, u1: Self::Mask) -> Self {
@@ -19,10 +125,76 @@ impl<const N: usize> FromMask for Doubled<F32x<N>> {
     }
 }
 
+/// Exponents of the leading bits of finite non-zero lanes, and zero in the
+/// other lanes.
+#[inline]
+fn ilogbk<const N: usize>(x: F32x<N>) -> I32x<N> {
+    let special = x.simd_eq(F32x::splat(0.)) | !x.is_finite();
+    let sub = x.abs().simd_lt(F32x::splat(f32::MIN_POSITIVE));
+    let y = sub.select(x * F32x::splat((1u64 << 24) as f32), x);
+    let e = ((y.to_bits() >> U32x::splat(23)) & U32x::splat(0xff)).cast::<i32>()
+        - sub.select(I32x::splat(127 + 24), I32x::splat(127));
+    special.select(I32x::splat(0), e)
+}
+
+/// `2^e` for `e` in the normal exponent range.
+#[inline]
+fn pow2k<const N: usize>(e: I32x<N>) -> F32x<N> {
+    F32x::from_bits((e + I32x::splat(127)).cast::<u32>() << U32x::splat(23))
+}
+
+/// `x * 2^e` with a single rounding, for `|e|` up to three times the exponent
+/// range.
+///
+/// Scaling by a power of two rounds only when the product is subnormal, so the
+/// factors above one come first and, for negative `e`, the last factor takes
+/// as much of the scaling as it can. The product before it is then normal,
+/// unless the result underflows to zero anyway.
+#[inline]
+fn ldexpk<const N: usize>(x: F32x<N>, e: I32x<N>) -> F32x<N> {
+    let c = e.simd_lt(I32x::splat(0)).select(
+        e.simd_max(I32x::splat(-126)),
+        e - e / I32x::splat(3) * I32x::splat(2),
+    );
+    let e1 = (e - c) / I32x::splat(2);
+    x * pow2k(e1) * pow2k(e - c - e1) * pow2k(c)
+}
+
+/// `(t + err) * 2^e` with a single rounding, for a subnormal result and `err`
+/// at most half an ulp of `t`.
+///
+/// Rounding `t` alone is correct unless its scaled value is an exact tie
+/// between two subnormals, which `err` then breaks.
+#[inline]
+fn ldexp_sum<const N: usize>(t: F32x<N>, err: F32x<N>, e: I32x<N>) -> F32x<N> {
+    let zero = F32x::splat(0.);
+    let r = ldexpk(t, e);
+    // Exact, as `r` is the nearest subnormal to `t` once scaled.
+    let d = t - ldexpk(r, -e);
+    let g = ldexpk(d + d, e);
+    let tie = g.abs().simd_eq(F32x::splat(f32::from_bits(1))) & ldexpk(g, -e).simd_eq(d + d);
+    let away = err.simd_ne(zero) & !(err.simd_gt(zero) ^ d.simd_gt(zero));
+    (tie & away).select(r + g, r)
+}
+
+impl<const N: usize> core::convert::From<[Doubled<f32>; N]> for Doubled<F32x<N>> {
+    #[inline]
+    fn from(array: [Doubled<f32>; N]) -> Self {
+        Self::from_array(array)
+    }
+}
+
+impl<const N: usize> core::convert::From<Doubled<F32x<N>>> for [Doubled<f32>; N] {
+    #[inline]
+    fn from(f: Doubled<F32x<N>>) -> Self {
+        f.to_array()
+    }
+}
+
 impl<const N: usize> core::convert::From<F32x<N>> for Doubled<F32x<N>> {
     #[inline]
     fn from(f: F32x<N>) -> Self {
-        Self::new(f, F32x::splat(0.))
+        Self::new(f, signed_zero(f))
     }
 }
 
@@ -43,267 +215,413 @@ impl<const N: usize> Doubled<F32x<N>> {
     }
 
     #[inline]
-    pub fn abs(self) -> Self {
+    pub fn from_array(array: [Doubled<f32>; N]) -> Self {
         Self::new(
-            self.0.abs(),
-            F32x::from_bits(self.1.to_bits() ^ (self.0.to_bits() & F32x::splat(-0.).to_bits())),
+            F32x::from_array(core::array::from_fn(|i| array[i].0)),
+            F32x::from_array(core::array::from_fn(|i| array[i].1)),
         )
     }
 
-    #[cfg(target_feature = "fma")]
     #[inline]
-    pub fn square(self) -> Self {
-        let r0 = self.0 * self.0;
-        Self::new(
-            r0,
-            (self.0 + self.0).mul_add(self.1, self.0.mul_add(self.0, -r0)),
-        )
+    pub fn to_array(self) -> [Doubled<f32>; N] {
+        let (hi, lo) = (self.0.to_array(), self.1.to_array());
+        core::array::from_fn(|i| Doubled::new(hi[i], lo[i]))
     }
-    #[cfg(not(target_feature = "fma"))]
+
+    /// Returns the value of lane `lane`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lane >= N`.
     #[inline]
-    pub fn square(self) -> Self {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let r0 = self.0 * self.0;
-        Self::new(
-            r0,
-            xh * xh + (-r0) + (xh + xh) * xl + xl * xl + self.0 * (self.1 + self.1),
-        )
+    pub fn extract(self, lane: usize) -> Doubled<f32> {
+        Doubled::new(self.0[lane], self.1[lane])
     }
 
-    #[cfg(target_feature = "fma")]
+    /// Returns a copy of `self` with lane `lane` set to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lane >= N`.
     #[inline]
-    pub fn square_as_f(self) -> F32x<N> {
-        self.0.mul_add(self.0, self.0 * self.1 + self.0 * self.1)
+    pub fn replace(mut self, lane: usize, value: Doubled<f32>) -> Self {
+        self.0[lane] = value.0;
+        self.1[lane] = value.1;
+        self
     }
-    #[cfg(not(target_feature = "fma"))]
+
+    /// Lane-wise [`Doubled::<f32>::to_f64`].
     #[inline]
-    pub fn square_as_f(self) -> F32x<N> {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        xh * self.1 + xh * self.1 + xl * xl + (xh * xl + xh * xl) + xh * xh
+    pub fn to_f64(self) -> F64x<N> {
+        self.0.cast::<f64>() + self.1.cast::<f64>()
     }
+}
 
-    #[cfg(feature = "enable_recsqrt_sp")]
+/// `x.0 + x.1` rounded once to `f32` in every lane, for normalized `x`.
+#[inline]
+fn round_to_f32x<const N: usize>(x: Doubled<F64x<N>>) -> F32x<N> {
+    let s = x.0 + x.1;
+    let bits = s.to_bits();
+    let even = (bits & U64x::splat(1)).simd_eq(U64x::splat(0));
+    let inexact = x.1.simd_ne(F64x::splat(0.)) & even & s.is_finite();
+    let up = x.1.is_sign_positive().simd_eq(s.is_sign_positive());
+    let bits = up.select(bits + U64x::splat(1), bits - U64x::splat(1));
+    inexact.select(F64x::from_bits(bits), s).cast::<f32>()
+}
+
+/// Lane-wise rounding, as for `Doubled<f32>` from `Doubled<f64>`.
+impl<const N: usize> core::convert::From<Doubled<F64x<N>>> for Doubled<F32x<N>> {
     #[inline]
-    pub fn sqrt(self) -> Self {
-        let x = (self.0 + self.1).rsqrte();
-        let r = self * x;
-        (r * (r * x + F32x::splat(-3.0))).scale(F32x::splat(-0.5))
+    fn from(f: Doubled<F64x<N>>) -> Self {
+        let x = f.0.cast::<f32>();
+        fix_special(x, round_to_f32x(eft::two_sum(f.0 - x.cast::<f64>(), f.1)))
     }
-    #[cfg(not(feature = "enable_recsqrt_sp"))]
+}
+
+/// Lane-wise exact widening, as for `Doubled<f64>` from `Doubled<f32>`.
+impl<const N: usize> core::convert::From<Doubled<F32x<N>>> for Doubled<F64x<N>> {
     #[inline]
-    pub fn sqrt(self) -> Self {
-        let t = (self.0 + self.1).sqrt();
-        ((self + t.mul_as_doubled(t)) * t.recip_as_doubled()).scale(F32x::splat(0.5))
+    fn from(f: Doubled<F32x<N>>) -> Self {
+        let r = eft::two_sum(f.0.cast::<f64>(), f.1.cast::<f64>());
+        fix_special(r.0, r.1)
     }
+}
 
-    #[cfg(target_feature = "fma")]
+impl<const N: usize> core::convert::From<f64> for Doubled<F32x<N>> {
+    fn from(d: f64) -> Self {
+        Self::splat(Doubled::<f32>::from_f64(d))
+    }
+}
+
+// Loads and stores. The `interleaved` variants work on array-of-structs data
+// (`[Doubled<f32>]`), the `split` ones on separate slices of high and low words.
+impl<const N: usize> Doubled<F32x<N>> {
+    /// Loads the first `N` elements of `xs`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `xs.len() < N`.
     #[inline]
-    pub fn mul_as_f(self, other: Self) -> F32x<N> {
-        self.0
-            .mul_add(other.0, self.1.mul_add(other.0, self.0 * other.1))
+    pub fn load_interleaved(xs: &[Doubled<f32>]) -> Self {
+        assert!(
+            xs.len() >= N,
+            "slice length must be at least the number of lanes"
+        );
+        Self::from_array(core::array::from_fn(|i| xs[i]))
     }
-    #[cfg(not(target_feature = "fma"))]
+
+    /// Loads up to `N` elements of `xs`, setting the missing lanes to zero.
     #[inline]
-    pub fn mul_as_f(self, other: Self) -> F32x<N> {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let yh = other.0.upper();
-        let yl = other.0 - yh;
-        self.1 * yh + xh * other.1 + xl * yl + xh * yl + xl * yh + xh * yh
+    pub fn load_interleaved_or_default(xs: &[Doubled<f32>]) -> Self {
+        Self::from_array(core::array::from_fn(|i| {
+            xs.get(i).copied().unwrap_or(Doubled::new(0., 0.))
+        }))
     }
-    #[cfg(target_feature = "fma")]
+
+    /// Stores the lanes into the first `N` elements of `out`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out.len() < N`.
     #[inline]
-    pub fn recip(self) -> Self {
-        let q0 = self.0.recip();
-        Self::new(
-            q0,
-            q0 * (-self.1).mul_add(q0, (-self.0).mul_add(q0, F32x::splat(1.))),
-        )
+    pub fn store_interleaved(self, out: &mut [Doubled<f32>]) {
+        assert!(
+            out.len() >= N,
+            "slice length must be at least the number of lanes"
+        );
+        out[..N].copy_from_slice(&self.to_array());
     }
-    #[cfg(not(target_feature = "fma"))]
+
+    /// Stores the lanes enabled by `enable`, skipping the ones past the end of `out`.
     #[inline]
-    pub fn recip(self) -> Self {
-        let t = self.0.recip();
-        let dh = self.0.upper();
-        let dl = self.0 - dh;
-        let th = t.upper();
-        let tl = t - th;
-        let q0 = t;
-        Self::new(
-            q0,
-            t * (F32x::splat(1.) - dh * th - dh * tl - dl * th - dl * tl - self.1 * t),
-        )
+    pub fn store_interleaved_select(self, out: &mut [Doubled<f32>], enable: M32x<N>) {
+        for (i, (o, x)) in out.iter_mut().zip(self.to_array()).enumerate() {
+            if enable.test(i) {
+                *o = x;
+            }
+        }
     }
-}
 
-impl<const N: usize> core::convert::From<f64> for Doubled<F32x<N>> {
-    fn from(d: f64) -> Self {
-        Self::splat(Doubled::<f32>::from_f64(d))
+    /// Loads the first `N` high words from `hi` and low words from `lo`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either slice is shorter than `N`.
+    #[inline]
+    pub fn load_split(hi: &[f32], lo: &[f32]) -> Self {
+        Self::new(F32x::from_slice(hi), F32x::from_slice(lo))
     }
-}
 
-impl<const N: usize> core::ops::Add<Doubled<F32x<N>>> for F32x<N> {
-    type Output = Doubled<F32x<N>>;
+    /// Loads up to `N` high and low words, setting the missing ones to zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hi` and `lo` have different lengths.
     #[inline]
-    fn add(self, other: Doubled<F32x<N>>) -> Self::Output {
-        let r0 = self + other.0;
-        let v = r0 - self;
-        Doubled::new(r0, self - (r0 - v) + (other.0 - v) + other.1)
+    pub fn load_split_or_default(hi: &[f32], lo: &[f32]) -> Self {
+        assert_eq!(
+            hi.len(),
+            lo.len(),
+            "high and low words must have the same length"
+        );
+        Self::new(F32x::load_or_default(hi), F32x::load_or_default(lo))
     }
-}
 
-impl<const N: usize> core::ops::Mul for Doubled<F32x<N>> {
-    type Output = Self;
-    #[cfg(target_feature = "fma")]
+    /// Stores the high words into `hi` and the low words into `lo`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either slice is shorter than `N`.
     #[inline]
-    fn mul(self, other: Self) -> Self {
-        let r0 = self.0 * other.0;
-        Self::new(
-            r0,
-            self.0.mul_add(
-                other.1,
-                self.1.mul_add(other.0, self.0.mul_add(other.0, -r0)),
-            ),
-        )
+    pub fn store_split(self, hi: &mut [f32], lo: &mut [f32]) {
+        self.0.copy_to_slice(hi);
+        self.1.copy_to_slice(lo);
     }
-    #[cfg(not(target_feature = "fma"))]
+
+    /// Stores the lanes enabled by `enable`, skipping the ones past the end of
+    /// the slices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hi` and `lo` have different lengths.
     #[inline]
-    fn mul(self, other: Self) -> Self {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let yh = other.0.upper();
-        let yl = other.0 - yh;
-        let r0 = self.0 * other.0;
+    pub fn store_split_select(self, hi: &mut [f32], lo: &mut [f32], enable: M32x<N>) {
+        assert_eq!(
+            hi.len(),
+            lo.len(),
+            "high and low words must have the same length"
+        );
+        self.0.store_select(hi, enable);
+        self.1.store_select(lo, enable);
+    }
+
+    /// Reads `xs[idxs[i]]` into lane `i`. Out-of-bounds indices give zero.
+    #[inline]
+    pub fn gather_interleaved(xs: &[Doubled<f32>], idxs: Usizex<N>) -> Self {
+        Self::from_array(core::array::from_fn(|i| {
+            xs.get(idxs[i]).copied().unwrap_or(Doubled::new(0., 0.))
+        }))
+    }
+
+    /// Reads `hi[idxs[i]]` and `lo[idxs[i]]` into lane `i`. Out-of-bounds
+    /// indices give zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hi` and `lo` have different lengths.
+    #[inline]
+    pub fn gather_split(hi: &[f32], lo: &[f32], idxs: Usizex<N>) -> Self {
+        assert_eq!(
+            hi.len(),
+            lo.len(),
+            "high and low words must have the same length"
+        );
         Self::new(
-            r0,
-            xh * yh + (-r0) + xl * yh + xh * yl + xl * yl + self.0 * other.1 + self.1 * other.0,
+            F32x::gather_or_default(hi, idxs),
+            F32x::gather_or_default(lo, idxs),
         )
     }
+
+    /// Writes lane `i` into `out[idxs[i]]`. Out-of-bounds indices are skipped;
+    /// for repeated indices the last lane wins.
+    #[inline]
+    pub fn scatter_interleaved(self, out: &mut [Doubled<f32>], idxs: Usizex<N>) {
+        for (i, x) in self.to_array().into_iter().enumerate() {
+            if let Some(o) = out.get_mut(idxs[i]) {
+                *o = x;
+            }
+        }
+    }
+
+    /// Writes lane `i` into `hi[idxs[i]]` and `lo[idxs[i]]`. Out-of-bounds
+    /// indices are skipped; for repeated indices the last lane wins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hi` and `lo` have different lengths.
+    #[inline]
+    pub fn scatter_split(self, hi: &mut [f32], lo: &mut [f32], idxs: Usizex<N>) {
+        assert_eq!(
+            hi.len(),
+            lo.len(),
+            "high and low words must have the same length"
+        );
+        self.0.scatter(hi, idxs);
+        self.1.scatter(lo, idxs);
+    }
 }
 
-impl<const N: usize> core::ops::Mul<F32x<N>> for Doubled<F32x<N>> {
-    type Output = Self;
-    #[cfg(target_feature = "fma")]
+// Horizontal reductions. Lanes are combined pairwise in double-word arithmetic,
+// so the low words take part in every step.
+impl<const N: usize> Doubled<F32x<N>> {
     #[inline]
-    fn mul(self, other: F32x<N>) -> Self {
-        let r0 = self.0 * other;
-        Self::new(r0, self.1.mul_add(other, self.0.mul_add(other, -r0)))
+    pub fn reduce_sum(self) -> Doubled<f32> {
+        self.reduce(|a, b| (a + b).normalize())
     }
-    #[cfg(not(target_feature = "fma"))]
+
     #[inline]
-    fn mul(self, other: F32x<N>) -> Self {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let yh = other.upper();
-        let yl = other - yh;
-        let r0 = self.0 * other;
-        Self::new(
-            r0,
-            xh * yh + (-r0) + xl * yh + xh * yl + xl * yl + self.1 * other,
-        )
+    pub fn reduce_product(self) -> Doubled<f32> {
+        self.reduce(|a, b| (a * b).normalize())
+    }
+
+    /// Returns the minimum lane. NaN lanes are ignored unless all lanes are NaN.
+    #[inline]
+    pub fn reduce_min(self) -> Doubled<f32> {
+        self.reduce(|a, b| {
+            if a.0.is_nan() || b.0 < a.0 || (b.0 == a.0 && b.1 < a.1) {
+                b
+            } else {
+                a
+            }
+        })
+    }
+
+    /// Returns the maximum lane. NaN lanes are ignored unless all lanes are NaN.
+    #[inline]
+    pub fn reduce_max(self) -> Doubled<f32> {
+        self.reduce(|a, b| {
+            if a.0.is_nan() || b.0 > a.0 || (b.0 == a.0 && b.1 > a.1) {
+                b
+            } else {
+                a
+            }
+        })
+    }
+
+    #[inline]
+    fn reduce(self, f: impl Fn(Doubled<f32>, Doubled<f32>) -> Doubled<f32>) -> Doubled<f32> {
+        let mut lanes = self.to_array();
+        let mut step = 1;
+        while step < N {
+            let mut i = 0;
+            while i + step < N {
+                lanes[i] = f(lanes[i], lanes[i + step]);
+                i += 2 * step;
+            }
+            step *= 2;
+        }
+        lanes[0]
     }
 }
 
-impl<const N: usize> core::ops::Div for Doubled<F32x<N>> {
-    type Output = Self;
-    #[cfg(target_feature = "fma")]
+// Lane-wise comparisons. Both words take part, so for normalized values the
+// result is the one of comparing the exact sums `x.0 + x.1`.
+impl<const N: usize> Doubled<F32x<N>> {
     #[inline]
-    fn div(self, other: Self) -> Self {
-        let t = other.0.recip();
+    pub fn simd_eq(self, other: Self) -> M32x<N> {
+        self.0.simd_eq(other.0) & self.1.simd_eq(other.1)
+    }
 
-        let q0 = self.0 * t;
-        let u = t.mul_add(self.0, -q0);
-        let mut q1 = (-other.1).mul_add(t, (-other.0).mul_add(t, F32x::splat(1.)));
-        q1 = q0.mul_add(q1, self.1.mul_add(t, u));
+    #[inline]
+    pub fn simd_ne(self, other: Self) -> M32x<N> {
+        !self.simd_eq(other)
+    }
 
-        Self::new(q0, q1)
+    #[inline]
+    pub fn simd_lt(self, other: Self) -> M32x<N> {
+        self.0.simd_lt(other.0) | (self.0.simd_eq(other.0) & self.1.simd_lt(other.1))
     }
-    #[cfg(not(target_feature = "fma"))]
+
     #[inline]
-    fn div(self, other: Self) -> Self {
-        let t = other.0.recip();
-        let dh = other.0.upper();
-        let dl = other.0 - dh;
-        let th = t.upper();
-        let tl = t - th;
-        let nhh = self.0.upper();
-        let nhl = self.0 - nhh;
+    pub fn simd_le(self, other: Self) -> M32x<N> {
+        self.0.simd_lt(other.0) | (self.0.simd_eq(other.0) & self.1.simd_le(other.1))
+    }
 
-        let q0 = self.0 * t;
+    #[inline]
+    pub fn simd_gt(self, other: Self) -> M32x<N> {
+        other.simd_lt(self)
+    }
 
-        let u = nhh * th - q0
-            + nhh * tl
-            + nhl * th
-            + nhl * tl
-            + q0 * (F32x::splat(1.) - dh * th - dh * tl - dl * th - dl * tl);
+    #[inline]
+    pub fn simd_ge(self, other: Self) -> M32x<N> {
+        other.simd_le(self)
+    }
 
-        Self::new(q0, t * (self.1 - q0 * other.1) + u)
+    #[inline]
+    pub fn is_nan(self) -> M32x<N> {
+        self.0.is_nan() | self.1.is_nan()
     }
-}
 
-impl<const N: usize> CheckOrder for Doubled<F32x<N>> {
-    fn check_order(self, _other: Self) {}
+    #[inline]
+    pub fn is_finite(self) -> M32x<N> {
+        self.0.is_finite() & self.1.is_finite()
+    }
 }
 
-impl<const N: usize> CheckOrder<F32x<N>> for Doubled<F32x<N>> {
-    fn check_order(self, _other: F32x<N>) {}
-}
+// Power-of-two scaling and exponent extraction, lane-wise versions of the
+// scalar methods.
+impl<const N: usize> Doubled<F32x<N>> {
+    #[inline]
+    pub fn ldexp(self, e: I32x<N>) -> Self {
+        let e = e.simd_clamp(I32x::splat(-300), I32x::splat(300));
+        let hi = ldexpk(self.0, e);
+        let sub = hi.abs().simd_le(F32x::splat(f32::MIN_POSITIVE));
+        let r = eft::two_sum(self.0 - ldexpk(hi, -e), self.1);
+        let lo = sub.select(ldexp_sum(r.0, r.1, e), ldexpk(self.1, e));
+        let s = (hi + lo).simd_eq(F32x::splat(0.)).select(hi, hi + lo);
+        fix_special(s, hi - s + lo)
+    }
 
-impl<const N: usize> CheckOrder<Doubled<F32x<N>>> for F32x<N> {
-    fn check_order(self, _other: Doubled<F32x<N>>) {}
-}
+    #[inline]
+    pub fn scalbn(self, e: I32x<N>) -> Self {
+        self.ldexp(e)
+    }
 
-impl<const N: usize> CheckOrder for F32x<N> {
-    fn check_order(self, _other: Self) {}
-}
+    #[inline]
+    pub fn ilogb(self) -> I32x<N> {
+        let e = ilogbk(self.0);
+        let below = (self.0.to_bits() & U32x::splat(0x007f_ffff)).simd_eq(U32x::splat(0))
+            & self.1.simd_ne(F32x::splat(0.))
+            & (self.1.is_sign_negative() ^ self.0.is_sign_negative());
+        let e = below.select(e - I32x::splat(1), e);
+        let e =
+            (self.0.simd_eq(F32x::splat(0.)) | self.0.is_nan()).select(I32x::splat(i32::MIN), e);
+        self.0.is_infinite().select(I32x::splat(i32::MAX), e)
+    }
 
-impl<const N: usize> AsDoubled for F32x<N> {
     #[inline]
-    fn as_doubled(self) -> Doubled<Self> {
-        Doubled::new(self, Self::splat(0.))
+    pub fn frexp(self) -> (Self, I32x<N>) {
+        let special = self.0.simd_eq(F32x::splat(0.)) | !self.0.is_finite();
+        let e = special.select(I32x::splat(0), self.ilogb() + I32x::splat(1));
+        (Self::select(special, self, self.ldexp(-e)), e)
     }
 }
 
-impl<const N: usize> MulAsDoubled for F32x<N> {
-    #[cfg(target_feature = "fma")]
+impl<const N: usize> core::ops::Add<Doubled<F32x<N>>> for F32x<N> {
+    type Output = Doubled<F32x<N>>;
     #[inline]
-    fn mul_as_doubled(self, other: Self) -> Doubled<Self> {
-        let r0 = self * other;
-        Doubled::new(r0, self.mul_add(other, -r0))
+    fn add(self, other: Doubled<F32x<N>>) -> Self::Output {
+        let r0 = self + other.0;
+        let v = r0 - self;
+        Doubled::new(r0, self - (r0 - v) + (other.0 - v) + other.1)
     }
-    #[cfg(not(target_feature = "fma"))]
+}
+
+impl<const N: usize> CheckOrder for F32x<N> {
     #[inline]
-    fn mul_as_doubled(self, other: Self) -> Doubled<Self> {
-        let xh = self.upper();
-        let xl = self - xh;
-        let yh = other.upper();
-        let yl = other - yh;
-        let r0 = self * other;
-        Doubled::new(r0, xh * yh + (-r0) + xl * yh + xh * yl + xl * yl)
+    fn check_order(self, other: Self) {
+        if cfg!(debug_assertions) {
+            let ok = !self.is_finite() | !other.is_finite() | self.abs().simd_ge(other.abs());
+            if let Some(lane) = (!ok).first_set() {
+                panic!(
+                    "check_order failed in lane {lane}: |{}| < |{}|",
+                    self[lane], other[lane]
+                );
+            }
+        }
     }
 }
 
-impl<const N: usize> RecipAsDoubled for F32x<N> {
-    #[cfg(target_feature = "fma")]
-    #[inline]
-    fn recip_as_doubled(self) -> Doubled<Self> {
-        let q0 = self.recip();
-        Doubled::new(q0, q0 * (-self).mul_add(q0, Self::splat(1.)))
-    }
-    #[cfg(not(target_feature = "fma"))]
-    #[inline]
-    fn recip_as_doubled(self) -> Doubled<Self> {
-        let t = self.recip();
-        let dh = self.upper();
-        let dl = self - dh;
-        let th = t.upper();
-        let tl = t - th;
-        let q0 = t;
-        Doubled::new(
-            q0,
-            t * (Self::splat(1.) - dh * th - dh * tl - dl * th - dl * tl),
+impl<const N: usize> DoubledRandom for Doubled<F32x<N>> {
+    #[inline]
+    fn random<F: FnMut() -> u64>(next: &mut F) -> Self {
+        let bits = Simd::<u64, N>::from_array(core::array::from_fn(|_| next()));
+        let hi = bits >> Simd::splat(40);
+        let lo = (bits >> Simd::splat(16)) & Simd::splat(0xff_ffff);
+        Self::new(
+            hi.cast::<f32>() * pow2k(I32x::splat(-24)),
+            lo.cast::<f32>() * pow2k(I32x::splat(-48)),
         )
+        .normalize()
     }
 }
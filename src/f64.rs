@@ -1,4 +1,5 @@
 use super::*;
+use crate::base::{fix_special, signed_zero};
 
 impl Upper for f64 {
     #[inline]
@@ -7,6 +8,107 @@ impl Upper for f64 {
     }
 }
 
+impl eft::EftFloat for f64 {
+    #[inline]
+    fn splitter() -> Self {
+        134_217_729.
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        f64::mul_add(self, a, b)
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+}
+
+impl base::DoubledBase for f64 {
+    type Bits = u64;
+    type Mask = bool;
+    type Exp = i32;
+
+    const FMA: bool = false;
+
+    #[inline]
+    fn splat(value: f64) -> Self {
+        value
+    }
+
+    /// The high word of `-0` is left as is.
+    #[inline]
+    fn abs_doubled(x: Doubled<Self>) -> Doubled<Self> {
+        if x.0 < 0. {
+            Doubled::new(-x.0, -x.1)
+        } else {
+            x
+        }
+    }
+
+    #[inline]
+    fn to_bits(self) -> u64 {
+        f64::to_bits(self)
+    }
+
+    #[inline]
+    fn from_bits(bits: u64) -> Self {
+        f64::from_bits(bits)
+    }
+
+    #[inline]
+    fn abs(self) -> Self {
+        fabsk(self)
+    }
+
+    #[inline]
+    fn recip(self) -> Self {
+        1. / self
+    }
+
+    #[inline]
+    fn copysign(self, sign: Self) -> Self {
+        f64::copysign(self, sign)
+    }
+
+    #[inline]
+    fn simd_eq(self, other: Self) -> bool {
+        self == other
+    }
+
+    #[inline]
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+
+    #[inline]
+    fn select(mask: bool, true_values: Self, false_values: Self) -> Self {
+        if mask {
+            true_values
+        } else {
+            false_values
+        }
+    }
+
+    #[inline]
+    fn exponent(self) -> i32 {
+        ilogbk(self)
+    }
+
+    #[inline]
+    fn mul_pow2(self, e: i32) -> Self {
+        ldexpk(self, e)
+    }
+
+    #[inline]
+    fn ldexp_doubled(x: Doubled<Self>, e: i32) -> Doubled<Self> {
+        x.ldexp(e)
+    }
+}
+
 impl FromMask for Doubled<f64> {
     type Mask = u64;
     fn from_mask(u0: Self::Mask, u1: Self::Mask) -> Self {
@@ -25,10 +127,67 @@ fn fabsk(x: f64) -> f64 {
     f64::from_bits(0x7fff_ffff_ffff_ffff & x.to_bits())
 }
 
+/// `2^e` for `e` in the range of finite values, including subnormals.
+#[inline]
+fn pow2(e: i32) -> f64 {
+    if e >= -1022 {
+        f64::from_bits(((e + 1023) as u64) << 52)
+    } else {
+        f64::from_bits(1 << (e + 1074))
+    }
+}
+
+/// Exponent of the leading bit of a finite non-zero `x`, and zero otherwise.
+#[inline]
+fn ilogbk(x: f64) -> i32 {
+    let bits = x.to_bits();
+    let e = ((bits >> 52) & 0x7ff) as i32;
+    if x == 0. || e == 0x7ff {
+        0
+    } else if e == 0 {
+        -1011 - (bits & 0x000f_ffff_ffff_ffff).leading_zeros() as i32
+    } else {
+        e - 1023
+    }
+}
+
+/// `x * 2^e` with a single rounding, for `|e|` up to three times the exponent
+/// range.
+///
+/// Scaling by a power of two rounds only when the product is subnormal, so the
+/// factors above one come first and, for negative `e`, the last factor takes
+/// as much of the scaling as it can. The product before it is then normal,
+/// unless the result underflows to zero anyway.
+#[inline]
+fn ldexpk(x: f64, e: i32) -> f64 {
+    let c = if e < 0 { e.max(-1022) } else { e - 2 * (e / 3) };
+    let e1 = (e - c) / 2;
+    x * pow2(e1) * pow2(e - c - e1) * pow2(c)
+}
+
+/// `(t + err) * 2^e` with a single rounding, for a subnormal result and `err`
+/// at most half an ulp of `t`.
+///
+/// Rounding `t` alone is correct unless its scaled value is an exact tie
+/// between two subnormals, which `err` then breaks.
+#[inline]
+fn ldexp_sum(t: f64, err: f64, e: i32) -> f64 {
+    let r = ldexpk(t, e);
+    // Exact, as `r` is the nearest subnormal to `t` once scaled.
+    let d = t - ldexpk(r, -e);
+    let g = ldexpk(d + d, e);
+    let tie = fabsk(g) == pow2(-1074) && ldexpk(g, -e) == d + d;
+    if tie && err != 0. && (err > 0.) == (d > 0.) {
+        r + g
+    } else {
+        r
+    }
+}
+
 impl core::convert::From<f64> for Doubled<f64> {
     #[inline]
     fn from(f: f64) -> Self {
-        Self::new(f, 0.)
+        Self::new(f, signed_zero(f))
     }
 }
 
@@ -40,74 +199,118 @@ impl core::convert::From<Doubled<f64>> for f64 {
 }
 
 impl Doubled<f64> {
-    #[inline]
-    pub fn abs(self) -> Self {
-        if self.0 < 0. {
-            Self::new(-self.0, -self.1)
-        } else {
-            self
+    /// Spacing of the double-word format at the value of `self`.
+    ///
+    /// This is the ulp of a binary format with a 106-bit significand, but
+    /// never finer than the smallest subnormal `f64`. It is infinite for
+    /// infinities and NaN for NaN.
+    pub fn ulp(self) -> f64 {
+        if !self.0.is_finite() {
+            return fabsk(self.0);
         }
+        let bits = self.0.to_bits();
+        let biased = ((bits >> 52) & 0x7ff) as i32;
+        if biased == 0 {
+            return pow2(-1074);
+        }
+        let mut e = biased - 1023;
+        // Just below a power of two the value lies in the binade below.
+        if bits & 0x000f_ffff_ffff_ffff == 0 && self.1 != 0. && (self.1 < 0.) != (self.0 < 0.) {
+            e -= 1;
+        }
+        pow2((e - 105).max(-1074))
     }
 
-    #[inline]
-    pub fn square(self) -> Self {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let r0 = self.0 * self.0;
-        Self::new(
-            r0,
-            xh * xh - r0 + (xh + xh) * xl + xl * xl + self.0 * (self.1 + self.1),
-        )
+    /// The next double-word value towards positive infinity.
+    ///
+    /// The low word is stepped by [`ulp`](Self::ulp) and the pair is normalized.
+    pub fn next_up(self) -> Self {
+        let mut u = self.ulp();
+        // From a negative power of two, the step lands in the binade below.
+        if self.0 < 0. && self.1 == 0. && self.0.to_bits() & 0x000f_ffff_ffff_ffff == 0 {
+            u = (u * 0.5).max(pow2(-1074));
+        }
+        Self::new(self.0, self.1 + u).normalize()
     }
 
-    #[inline]
-    pub fn square_as_f(self) -> f64 {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        xh * self.1 + xh * self.1 + xl * xl + (xh * xl + xh * xl) + xh * xh
+    /// The next double-word value towards negative infinity.
+    ///
+    /// The low word is stepped by [`ulp`](Self::ulp) and the pair is normalized.
+    pub fn next_down(self) -> Self {
+        -(-self).next_up()
     }
 
-    #[inline]
-    pub fn mul_as_f(self, other: Self) -> f64 {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let yh = other.0.upper();
-        let yl = other.0 - yh;
-        self.1 * yh + xh * other.1 + xl * yl + xh * yl + xl * yh + xh * yh
+    /// Distance between `self` and `other` in ulps of the one of larger magnitude.
+    pub fn ulp_distance(self, other: Self) -> f64 {
+        let d = self - other;
+        let m = self.abs() - other.abs();
+        let u = if m.0 + m.1 >= 0. {
+            self.ulp()
+        } else {
+            other.ulp()
+        };
+        fabsk(d.0 + d.1) / u
     }
+}
 
-    pub fn recip(self) -> Doubled<f64> {
-        let t = 1. / self.0;
-        let dh = self.0.upper();
-        let dl = self.0 - dh;
-        let th = t.upper();
-        let tl = t - th;
-        let q0 = t;
-        Self::new(
-            q0,
-            t * (1. - dh * th - dh * tl - dl * th - dl * tl - self.1 * t),
-        )
+// Power-of-two scaling and exponent extraction.
+impl Doubled<f64> {
+    /// `self * 2^e`.
+    ///
+    /// Each word is scaled with a single rounding. When the result falls into
+    /// the subnormal range, the residual of the high word and the low word are
+    /// added exactly, so that the sum is still rounded only once.
+    pub fn ldexp(self, e: i32) -> Self {
+        let e = e.clamp(-2200, 2200);
+        let hi = ldexpk(self.0, e);
+        let lo = if fabsk(hi) <= f64::MIN_POSITIVE {
+            let r = eft::two_sum(self.0 - ldexpk(hi, -e), self.1);
+            ldexp_sum(r.0, r.1, e)
+        } else {
+            ldexpk(self.1, e)
+        };
+        // A zero sum takes the sign of the high word, as `-0. * 2^e` would.
+        let s = if hi + lo == 0. { hi } else { hi + lo };
+        fix_special(s, hi - s + lo)
     }
-}
 
-impl CheckOrder for Doubled<f64> {
+    /// Same as [`ldexp`](Self::ldexp), the radix being two.
     #[inline]
-    fn check_order(self, other: Self) {
-        self.0.check_order(other.0)
+    pub fn scalbn(self, e: i32) -> Self {
+        self.ldexp(e)
     }
-}
 
-impl CheckOrder<f64> for Doubled<f64> {
-    #[inline]
-    fn check_order(self, other: f64) {
-        self.0.check_order(other)
+    /// Exponent of the leading bit of `self.0 + self.1`.
+    ///
+    /// Returns `i32::MIN` for zeros and NaN, and `i32::MAX` for infinities.
+    pub fn ilogb(self) -> i32 {
+        if self.0 == 0. || self.0.is_nan() {
+            return i32::MIN;
+        }
+        if self.0.is_infinite() {
+            return i32::MAX;
+        }
+        let e = ilogbk(self.0);
+        // Just below a power of two the value lies in the binade below.
+        if self.0.to_bits() & 0x000f_ffff_ffff_ffff == 0
+            && self.1 != 0.
+            && (self.1 < 0.) != (self.0 < 0.)
+        {
+            e - 1
+        } else {
+            e
+        }
     }
-}
 
-impl CheckOrder<Doubled<f64>> for f64 {
-    #[inline]
-    fn check_order(self, other: Doubled<f64>) {
-        self.check_order(other.0)
+    /// Splits `self` into `m * 2^e` with `|m|` in `[0.5, 1)`.
+    ///
+    /// Zeros, infinities and NaN are returned unchanged with a zero exponent.
+    pub fn frexp(self) -> (Self, i32) {
+        if self.0 == 0. || !self.0.is_finite() {
+            return (self, 0);
+        }
+        let e = self.ilogb() + 1;
+        (self.ldexp(-e), e)
     }
 }
 
@@ -133,90 +336,13 @@ impl core::ops::Add<Doubled<f64>> for f64 {
     }
 }
 
-impl core::ops::Mul for Doubled<f64> {
-    type Output = Self;
-    #[inline]
-    fn mul(self, other: Self) -> Self {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let yh = other.0.upper();
-        let yl = other.0 - yh;
-        let r0 = self.0 * other.0;
-        Self::new(
-            r0,
-            xh * yh - r0 + xl * yh + xh * yl + xl * yl + self.0 * other.1 + self.1 * other.0,
-        )
-    }
-}
-
-impl core::ops::Mul<f64> for Doubled<f64> {
-    type Output = Self;
-    #[inline]
-    fn mul(self, other: f64) -> Self {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let yh = other.upper();
-        let yl = other - yh;
-        let r0 = self.0 * other;
-        Self::new(
-            r0,
-            xh * yh - r0 + xl * yh + xh * yl + xl * yl + self.1 * other,
-        )
-    }
-}
-
-impl core::ops::Div for Doubled<f64> {
-    type Output = Self;
+impl DoubledRandom for Doubled<f64> {
+    /// Uses the upper 53 bits of two words from `next`. Values within `2^-54`
+    /// of one are normalized to a high word of one and a negative low word.
     #[inline]
-    fn div(self, other: Self) -> Self {
-        let t = 1. / other.0;
-        let dh = other.0.upper();
-        let dl = other.0 - dh;
-        let th = t.upper();
-        let tl = t - th;
-        let nhh = self.0.upper();
-        let nhl = self.0 - nhh;
-
-        let q0 = self.0 * t;
-
-        let u = -q0
-            + nhh * th
-            + nhh * tl
-            + nhl * th
-            + nhl * tl
-            + q0 * (1. - dh * th - dh * tl - dl * th - dl * tl);
-
-        Self::new(q0, t * (self.1 - q0 * other.1) + u)
-    }
-}
-
-impl AsDoubled for f64 {
-    #[inline]
-    fn as_doubled(self) -> Doubled<Self> {
-        Doubled::new(self, 0.)
-    }
-}
-
-impl MulAsDoubled for f64 {
-    #[inline]
-    fn mul_as_doubled(self, other: Self) -> Doubled<Self> {
-        let xh = self.upper();
-        let xl = self - xh;
-        let yh = other.upper();
-        let yl = other - yh;
-        let r0 = self * other;
-        Doubled::new(r0, xh * yh - r0 + xl * yh + xh * yl + xl * yl)
-    }
-}
-
-impl RecipAsDoubled for f64 {
-    fn recip_as_doubled(self) -> Doubled<f64> {
-        let t = 1. / self;
-        let dh = self.upper();
-        let dl = self - dh;
-        let th = t.upper();
-        let tl = t - th;
-        let q0 = t;
-        Doubled::new(q0, t * (1. - dh * th - dh * tl - dl * th - dl * tl))
+    fn random<F: FnMut() -> u64>(next: &mut F) -> Self {
+        let hi = next() >> 11;
+        let lo = next() >> 11;
+        Self::new(hi as f64 * pow2(-53), lo as f64 * pow2(-106)).normalize()
     }
 }
@@ -1,9 +1,15 @@
+use crate::base::{fix_special, signed_zero};
 use crate::*;
-use core::simd::num::SimdFloat;
-use std::simd::{Simd, StdFloat};
+use core::simd::cmp::{SimdOrd, SimdPartialEq, SimdPartialOrd};
+use core::simd::num::{SimdFloat, SimdInt, SimdUint};
+use std::simd::{Mask, Select, Simd, StdFloat};
 
 type F64x<const N: usize> = Simd<f64, N>;
 type U64x<const N: usize> = Simd<u64, N>;
+type I64x<const N: usize> = Simd<i64, N>;
+type I32x<const N: usize> = Simd<i32, N>;
+type M64x<const N: usize> = Mask<i64, N>;
+type Usizex<const N: usize> = Simd<usize, N>;
 
 impl<const N: usize> Upper for F64x<N> {
     #[inline]
@@ -12,6 +18,91 @@ impl<const N: usize> Upper for F64x<N> {
     }
 }
 
+impl<const N: usize> eft::EftFloat for F64x<N> {
+    #[inline]
+    fn splitter() -> Self {
+        Self::splat(134_217_729.)
+    }
+
+    #[inline]
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        StdFloat::mul_add(self, a, b)
+    }
+
+    #[inline]
+    fn sqrt(self) -> Self {
+        StdFloat::sqrt(self)
+    }
+}
+
+impl<const N: usize> base::DoubledBase for F64x<N> {
+    type Bits = U64x<N>;
+    type Mask = M64x<N>;
+    type Exp = I32x<N>;
+
+    const FMA: bool = cfg!(target_feature = "fma");
+
+    #[inline]
+    fn splat(value: f64) -> Self {
+        F64x::splat(value)
+    }
+
+    #[inline]
+    fn to_bits(self) -> U64x<N> {
+        SimdFloat::to_bits(self)
+    }
+
+    #[inline]
+    fn from_bits(bits: U64x<N>) -> Self {
+        SimdFloat::from_bits(bits)
+    }
+
+    #[inline]
+    fn abs(self) -> Self {
+        SimdFloat::abs(self)
+    }
+
+    #[inline]
+    fn recip(self) -> Self {
+        SimdFloat::recip(self)
+    }
+
+    #[inline]
+    fn copysign(self, sign: Self) -> Self {
+        SimdFloat::copysign(self, sign)
+    }
+
+    #[inline]
+    fn simd_eq(self, other: Self) -> M64x<N> {
+        SimdPartialEq::simd_eq(self, other)
+    }
+
+    #[inline]
+    fn is_finite(self) -> M64x<N> {
+        SimdFloat::is_finite(self)
+    }
+
+    #[inline]
+    fn select(mask: M64x<N>, true_values: Self, false_values: Self) -> Self {
+        mask.select(true_values, false_values)
+    }
+
+    #[inline]
+    fn exponent(self) -> I32x<N> {
+        ilogbk(self).cast::<i32>()
+    }
+
+    #[inline]
+    fn mul_pow2(self, e: I32x<N>) -> Self {
+        ldexpk(self, e.cast::<i64>())
+    }
+
+    #[inline]
+    fn ldexp_doubled(x: Doubled<Self>, e: I32x<N>) -> Doubled<Self> {
+        x.ldexp(e)
+    }
+}
+
 impl<const N: usize> FromMask for Doubled<F64x<N>> {
     type Mask = U64x<N>;
     fn from_mask(u0: Self::Mask, u1: Self::Mask) -> Self {
@@ -19,10 +110,76 @@ impl<const N: usize> FromMask for Doubled<F64x<N>> {
     }
 }
 
+/// Exponents of the leading bits of finite non-zero lanes, and zero in the
+/// other lanes.
+#[inline]
+fn ilogbk<const N: usize>(x: F64x<N>) -> I64x<N> {
+    let special = x.simd_eq(F64x::splat(0.)) | !x.is_finite();
+    let sub = x.abs().simd_lt(F64x::splat(f64::MIN_POSITIVE));
+    let y = sub.select(x * F64x::splat((1u64 << 54) as f64), x);
+    let e = ((y.to_bits() >> U64x::splat(52)) & U64x::splat(0x7ff)).cast::<i64>()
+        - sub.select(I64x::splat(1023 + 54), I64x::splat(1023));
+    special.select(I64x::splat(0), e)
+}
+
+/// `2^e` for `e` in the normal exponent range.
+#[inline]
+fn pow2k<const N: usize>(e: I64x<N>) -> F64x<N> {
+    F64x::from_bits((e + I64x::splat(1023)).cast::<u64>() << U64x::splat(52))
+}
+
+/// `x * 2^e` with a single rounding, for `|e|` up to three times the exponent
+/// range.
+///
+/// Scaling by a power of two rounds only when the product is subnormal, so the
+/// factors above one come first and, for negative `e`, the last factor takes
+/// as much of the scaling as it can. The product before it is then normal,
+/// unless the result underflows to zero anyway.
+#[inline]
+fn ldexpk<const N: usize>(x: F64x<N>, e: I64x<N>) -> F64x<N> {
+    let c = e.simd_lt(I64x::splat(0)).select(
+        e.simd_max(I64x::splat(-1022)),
+        e - e / I64x::splat(3) * I64x::splat(2),
+    );
+    let e1 = (e - c) / I64x::splat(2);
+    x * pow2k(e1) * pow2k(e - c - e1) * pow2k(c)
+}
+
+/// `(t + err) * 2^e` with a single rounding, for a subnormal result and `err`
+/// at most half an ulp of `t`.
+///
+/// Rounding `t` alone is correct unless its scaled value is an exact tie
+/// between two subnormals, which `err` then breaks.
+#[inline]
+fn ldexp_sum<const N: usize>(t: F64x<N>, err: F64x<N>, e: I64x<N>) -> F64x<N> {
+    let zero = F64x::splat(0.);
+    let r = ldexpk(t, e);
+    // Exact, as `r` is the nearest subnormal to `t` once scaled.
+    let d = t - ldexpk(r, -e);
+    let g = ldexpk(d + d, e);
+    let tie = g.abs().simd_eq(F64x::splat(f64::from_bits(1))) & ldexpk(g, -e).simd_eq(d + d);
+    let away = err.simd_ne(zero) & !(err.simd_gt(zero) ^ d.simd_gt(zero));
+    (tie & away).select(r + g, r)
+}
+
+impl<const N: usize> core::convert::From<[Doubled<f64>; N]> for Doubled<F64x<N>> {
+    #[inline]
+    fn from(array: [Doubled<f64>; N]) -> Self {
+        Self::from_array(array)
+    }
+}
+
+impl<const N: usize> core::convert::From<Doubled<F64x<N>>> for [Doubled<f64>; N] {
+    #[inline]
+    fn from(f: Doubled<F64x<N>>) -> Self {
+        f.to_array()
+    }
+}
+
 impl<const N: usize> core::convert::From<F64x<N>> for Doubled<F64x<N>> {
     #[inline]
     fn from(f: F64x<N>) -> Self {
-        Self::new(f, F64x::splat(0.))
+        Self::new(f, signed_zero(f))
     }
 }
 
@@ -43,255 +200,380 @@ impl<const N: usize> Doubled<F64x<N>> {
     }
 
     #[inline]
-    pub fn abs(self) -> Self {
+    pub fn from_array(array: [Doubled<f64>; N]) -> Self {
         Self::new(
-            self.0.abs(),
-            F64x::from_bits(self.1.to_bits() ^ (self.0.to_bits() & F64x::splat(-0.).to_bits())),
+            F64x::from_array(core::array::from_fn(|i| array[i].0)),
+            F64x::from_array(core::array::from_fn(|i| array[i].1)),
         )
     }
 
-    #[cfg(target_feature = "fma")]
     #[inline]
-    pub fn square(self) -> Self {
-        let r0 = self.0 * self.0;
-        Self::new(
-            r0,
-            (self.0 + self.0).mul_add(self.1, self.0.mul_add(self.0, -r0)),
-        )
+    pub fn to_array(self) -> [Doubled<f64>; N] {
+        let (hi, lo) = (self.0.to_array(), self.1.to_array());
+        core::array::from_fn(|i| Doubled::new(hi[i], lo[i]))
     }
-    #[cfg(not(target_feature = "fma"))]
+
+    /// Returns the value of lane `lane`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lane >= N`.
     #[inline]
-    pub fn square(self) -> Self {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let r0 = self.0 * self.0;
-        Self::new(
-            r0,
-            xh * xh + (-r0) + (xh + xh) * xl + xl * xl + self.0 * (self.1 + self.1),
-        )
+    pub fn extract(self, lane: usize) -> Doubled<f64> {
+        Doubled::new(self.0[lane], self.1[lane])
     }
 
-    #[cfg(target_feature = "fma")]
+    /// Returns a copy of `self` with lane `lane` set to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lane >= N`.
     #[inline]
-    pub fn square_as_f(self) -> F64x<N> {
-        self.0.mul_add(self.0, self.0 * self.1 + self.0 * self.1)
+    pub fn replace(mut self, lane: usize, value: Doubled<f64>) -> Self {
+        self.0[lane] = value.0;
+        self.1[lane] = value.1;
+        self
     }
-    #[cfg(not(target_feature = "fma"))]
+}
+
+// Loads and stores. The `interleaved` variants work on array-of-structs data
+// (`[Doubled<f64>]`), the `split` ones on separate slices of high and low words.
+impl<const N: usize> Doubled<F64x<N>> {
+    /// Loads the first `N` elements of `xs`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `xs.len() < N`.
     #[inline]
-    pub fn square_as_f(self) -> F64x<N> {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
+    pub fn load_interleaved(xs: &[Doubled<f64>]) -> Self {
+        assert!(
+            xs.len() >= N,
+            "slice length must be at least the number of lanes"
+        );
+        Self::from_array(core::array::from_fn(|i| xs[i]))
+    }
 
-        xh * self.1 + xh * self.1 + xl * xl + (xh * xl + xh * xl) + xh * xh
+    /// Loads up to `N` elements of `xs`, setting the missing lanes to zero.
+    #[inline]
+    pub fn load_interleaved_or_default(xs: &[Doubled<f64>]) -> Self {
+        Self::from_array(core::array::from_fn(|i| {
+            xs.get(i).copied().unwrap_or(Doubled::new(0., 0.))
+        }))
     }
 
+    /// Stores the lanes into the first `N` elements of `out`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out.len() < N`.
     #[inline]
-    pub fn sqrt(self) -> Self {
-        let t = (self.0 + self.1).sqrt();
-        ((self + t.mul_as_doubled(t)) * t.recip_as_doubled()).scale(F64x::splat(0.5))
+    pub fn store_interleaved(self, out: &mut [Doubled<f64>]) {
+        assert!(
+            out.len() >= N,
+            "slice length must be at least the number of lanes"
+        );
+        out[..N].copy_from_slice(&self.to_array());
     }
 
-    #[cfg(target_feature = "fma")]
+    /// Stores the lanes enabled by `enable`, skipping the ones past the end of `out`.
     #[inline]
-    pub fn mul_as_f(self, other: Self) -> F64x<N> {
-        self.0
-            .mul_add(other.0, self.1.mul_add(other.0, self.0 * other.1))
+    pub fn store_interleaved_select(self, out: &mut [Doubled<f64>], enable: M64x<N>) {
+        for (i, (o, x)) in out.iter_mut().zip(self.to_array()).enumerate() {
+            if enable.test(i) {
+                *o = x;
+            }
+        }
     }
-    #[cfg(not(target_feature = "fma"))]
+
+    /// Loads the first `N` high words from `hi` and low words from `lo`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either slice is shorter than `N`.
     #[inline]
-    pub fn mul_as_f(self, other: Self) -> F64x<N> {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let yh = other.0.upper();
-        let yl = other.0 - yh;
+    pub fn load_split(hi: &[f64], lo: &[f64]) -> Self {
+        Self::new(F64x::from_slice(hi), F64x::from_slice(lo))
+    }
 
-        self.1 * yh + xh * other.1 + xl * yl + xh * yl + xl * yh + xh * yh
+    /// Loads up to `N` high and low words, setting the missing ones to zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hi` and `lo` have different lengths.
+    #[inline]
+    pub fn load_split_or_default(hi: &[f64], lo: &[f64]) -> Self {
+        assert_eq!(
+            hi.len(),
+            lo.len(),
+            "high and low words must have the same length"
+        );
+        Self::new(F64x::load_or_default(hi), F64x::load_or_default(lo))
     }
-    #[cfg(target_feature = "fma")]
+
+    /// Stores the high words into `hi` and the low words into `lo`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either slice is shorter than `N`.
     #[inline]
-    pub fn recip(self) -> Self {
-        let q0 = self.0.recip();
-        Self::new(
-            q0,
-            q0 * (-self.1).mul_add(q0, (-self.0).mul_add(q0, F64x::splat(1.))),
-        )
+    pub fn store_split(self, hi: &mut [f64], lo: &mut [f64]) {
+        self.0.copy_to_slice(hi);
+        self.1.copy_to_slice(lo);
     }
-    #[cfg(not(target_feature = "fma"))]
+
+    /// Stores the lanes enabled by `enable`, skipping the ones past the end of
+    /// the slices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hi` and `lo` have different lengths.
     #[inline]
-    pub fn recip(self) -> Doubled<F64x<N>> {
-        let t = self.0.recip();
-        let dh = self.0.upper();
-        let dl = self.0 - dh;
-        let th = t.upper();
-        let tl = t - th;
-        let q0 = t;
-        Self::new(
-            q0,
-            t * (F64x::splat(1.) - dh * th - dh * tl - dl * th - dl * tl - self.1 * t),
-        )
+    pub fn store_split_select(self, hi: &mut [f64], lo: &mut [f64], enable: M64x<N>) {
+        assert_eq!(
+            hi.len(),
+            lo.len(),
+            "high and low words must have the same length"
+        );
+        self.0.store_select(hi, enable);
+        self.1.store_select(lo, enable);
     }
-}
 
-impl<const N: usize> core::ops::Add<Doubled<F64x<N>>> for F64x<N> {
-    type Output = Doubled<Self>;
+    /// Reads `xs[idxs[i]]` into lane `i`. Out-of-bounds indices give zero.
     #[inline]
-    fn add(self, other: Doubled<Self>) -> Self::Output {
-        let r0 = self + other.0;
-        let v = r0 - self;
-        Doubled::new(r0, self - (r0 - v) + (other.0 - v) + other.1)
+    pub fn gather_interleaved(xs: &[Doubled<f64>], idxs: Usizex<N>) -> Self {
+        Self::from_array(core::array::from_fn(|i| {
+            xs.get(idxs[i]).copied().unwrap_or(Doubled::new(0., 0.))
+        }))
     }
-}
 
-impl<const N: usize> core::ops::Mul for Doubled<F64x<N>> {
-    type Output = Self;
-    #[cfg(target_feature = "fma")]
+    /// Reads `hi[idxs[i]]` and `lo[idxs[i]]` into lane `i`. Out-of-bounds
+    /// indices give zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hi` and `lo` have different lengths.
     #[inline]
-    fn mul(self, other: Self) -> Self {
-        let r0 = self.0 * other.0;
+    pub fn gather_split(hi: &[f64], lo: &[f64], idxs: Usizex<N>) -> Self {
+        assert_eq!(
+            hi.len(),
+            lo.len(),
+            "high and low words must have the same length"
+        );
         Self::new(
-            r0,
-            self.0.mul_add(
-                other.1,
-                self.1.mul_add(other.0, self.0.mul_add(other.0, -r0)),
-            ),
+            F64x::gather_or_default(hi, idxs),
+            F64x::gather_or_default(lo, idxs),
         )
     }
-    #[cfg(not(target_feature = "fma"))]
+
+    /// Writes lane `i` into `out[idxs[i]]`. Out-of-bounds indices are skipped;
+    /// for repeated indices the last lane wins.
+    #[inline]
+    pub fn scatter_interleaved(self, out: &mut [Doubled<f64>], idxs: Usizex<N>) {
+        for (i, x) in self.to_array().into_iter().enumerate() {
+            if let Some(o) = out.get_mut(idxs[i]) {
+                *o = x;
+            }
+        }
+    }
+
+    /// Writes lane `i` into `hi[idxs[i]]` and `lo[idxs[i]]`. Out-of-bounds
+    /// indices are skipped; for repeated indices the last lane wins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hi` and `lo` have different lengths.
     #[inline]
-    fn mul(self, other: Self) -> Self {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let yh = other.0.upper();
-        let yl = other.0 - yh;
-        let r0 = self.0 * other.0;
-        Self::new(
-            r0,
-            xh * yh + (-r0) + xl * yh + xh * yl + xl * yl + self.0 * other.1 + self.1 * other.0,
-        )
+    pub fn scatter_split(self, hi: &mut [f64], lo: &mut [f64], idxs: Usizex<N>) {
+        assert_eq!(
+            hi.len(),
+            lo.len(),
+            "high and low words must have the same length"
+        );
+        self.0.scatter(hi, idxs);
+        self.1.scatter(lo, idxs);
     }
 }
 
-impl<const N: usize> core::ops::Mul<F64x<N>> for Doubled<F64x<N>> {
-    type Output = Self;
-    #[cfg(target_feature = "fma")]
+// Horizontal reductions. Lanes are combined pairwise in double-word arithmetic,
+// so the low words take part in every step.
+impl<const N: usize> Doubled<F64x<N>> {
     #[inline]
-    fn mul(self, other: F64x<N>) -> Self {
-        let r0 = self.0 * other;
-        Self::new(r0, self.1.mul_add(other, self.0.mul_add(other, -r0)))
+    pub fn reduce_sum(self) -> Doubled<f64> {
+        self.reduce(|a, b| (a + b).normalize())
     }
-    #[cfg(not(target_feature = "fma"))]
+
     #[inline]
-    fn mul(self, other: F64x<N>) -> Self {
-        let xh = self.0.upper();
-        let xl = self.0 - xh;
-        let yh = other.upper();
-        let yl = other - yh;
-        let r0 = self.0 * other;
-        Self::new(
-            r0,
-            xh * yh + (-r0) + xl * yh + xh * yl + xl * yl + self.1 * other,
-        )
+    pub fn reduce_product(self) -> Doubled<f64> {
+        self.reduce(|a, b| (a * b).normalize())
+    }
+
+    /// Returns the minimum lane. NaN lanes are ignored unless all lanes are NaN.
+    #[inline]
+    pub fn reduce_min(self) -> Doubled<f64> {
+        self.reduce(|a, b| {
+            if a.0.is_nan() || b.0 < a.0 || (b.0 == a.0 && b.1 < a.1) {
+                b
+            } else {
+                a
+            }
+        })
+    }
+
+    /// Returns the maximum lane. NaN lanes are ignored unless all lanes are NaN.
+    #[inline]
+    pub fn reduce_max(self) -> Doubled<f64> {
+        self.reduce(|a, b| {
+            if a.0.is_nan() || b.0 > a.0 || (b.0 == a.0 && b.1 > a.1) {
+                b
+            } else {
+                a
+            }
+        })
+    }
+
+    #[inline]
+    fn reduce(self, f: impl Fn(Doubled<f64>, Doubled<f64>) -> Doubled<f64>) -> Doubled<f64> {
+        let mut lanes = self.to_array();
+        let mut step = 1;
+        while step < N {
+            let mut i = 0;
+            while i + step < N {
+                lanes[i] = f(lanes[i], lanes[i + step]);
+                i += 2 * step;
+            }
+            step *= 2;
+        }
+        lanes[0]
     }
 }
 
-impl<const N: usize> core::ops::Div for Doubled<F64x<N>> {
-    type Output = Self;
-    #[cfg(target_feature = "fma")]
+// Lane-wise comparisons. Both words take part, so for normalized values the
+// result is the one of comparing the exact sums `x.0 + x.1`.
+impl<const N: usize> Doubled<F64x<N>> {
     #[inline]
-    fn div(self, other: Self) -> Self {
-        let t = other.0.recip();
+    pub fn simd_eq(self, other: Self) -> M64x<N> {
+        self.0.simd_eq(other.0) & self.1.simd_eq(other.1)
+    }
 
-        let q0 = self.0 * t;
-        let u = t.mul_add(self.0, -q0);
-        let mut q1 = (-other.1).mul_add(t, (-other.0).mul_add(t, F64x::splat(1.)));
-        q1 = q0.mul_add(q1, self.1.mul_add(t, u));
+    #[inline]
+    pub fn simd_ne(self, other: Self) -> M64x<N> {
+        !self.simd_eq(other)
+    }
 
-        Self::new(q0, q1)
+    #[inline]
+    pub fn simd_lt(self, other: Self) -> M64x<N> {
+        self.0.simd_lt(other.0) | (self.0.simd_eq(other.0) & self.1.simd_lt(other.1))
     }
-    #[cfg(not(target_feature = "fma"))]
+
     #[inline]
-    fn div(self, other: Self) -> Self {
-        let t = other.0.recip();
-        let dh = other.0.upper();
-        let dl = other.0 - dh;
-        let th = t.upper();
-        let tl = t - th;
-        let nhh = self.0.upper();
-        let nhl = self.0 - nhh;
+    pub fn simd_le(self, other: Self) -> M64x<N> {
+        self.0.simd_lt(other.0) | (self.0.simd_eq(other.0) & self.1.simd_le(other.1))
+    }
 
-        let q0 = self.0 * t;
+    #[inline]
+    pub fn simd_gt(self, other: Self) -> M64x<N> {
+        other.simd_lt(self)
+    }
 
-        let u = nhh * th - q0
-            + nhh * tl
-            + nhl * th
-            + nhl * tl
-            + q0 * (F64x::splat(1.) - dh * th - dh * tl - dl * th - dl * tl);
+    #[inline]
+    pub fn simd_ge(self, other: Self) -> M64x<N> {
+        other.simd_le(self)
+    }
 
-        Self::new(q0, t * (self.1 - q0 * other.1) + u)
+    #[inline]
+    pub fn is_nan(self) -> M64x<N> {
+        self.0.is_nan() | self.1.is_nan()
     }
-}
 
-impl<const N: usize> CheckOrder for Doubled<F64x<N>> {
-    fn check_order(self, _other: Self) {}
+    #[inline]
+    pub fn is_finite(self) -> M64x<N> {
+        self.0.is_finite() & self.1.is_finite()
+    }
 }
 
-impl<const N: usize> CheckOrder<F64x<N>> for Doubled<F64x<N>> {
-    fn check_order(self, _other: F64x<N>) {}
-}
+// Power-of-two scaling and exponent extraction, lane-wise versions of the
+// scalar methods.
+impl<const N: usize> Doubled<F64x<N>> {
+    #[inline]
+    pub fn ldexp(self, e: I32x<N>) -> Self {
+        let e = e
+            .simd_clamp(I32x::splat(-2200), I32x::splat(2200))
+            .cast::<i64>();
+        let hi = ldexpk(self.0, e);
+        let sub = hi.abs().simd_le(F64x::splat(f64::MIN_POSITIVE));
+        let r = eft::two_sum(self.0 - ldexpk(hi, -e), self.1);
+        let lo = sub.select(ldexp_sum(r.0, r.1, e), ldexpk(self.1, e));
+        let s = (hi + lo).simd_eq(F64x::splat(0.)).select(hi, hi + lo);
+        fix_special(s, hi - s + lo)
+    }
 
-impl<const N: usize> CheckOrder<Doubled<F64x<N>>> for F64x<N> {
-    fn check_order(self, _other: Doubled<F64x<N>>) {}
-}
+    #[inline]
+    pub fn scalbn(self, e: I32x<N>) -> Self {
+        self.ldexp(e)
+    }
 
-impl<const N: usize> CheckOrder for F64x<N> {
-    fn check_order(self, _other: Self) {}
-}
+    #[inline]
+    pub fn ilogb(self) -> I32x<N> {
+        let e = ilogbk(self.0);
+        let below = (self.0.to_bits() & U64x::splat(0x000f_ffff_ffff_ffff)).simd_eq(U64x::splat(0))
+            & self.1.simd_ne(F64x::splat(0.))
+            & (self.1.is_sign_negative() ^ self.0.is_sign_negative());
+        let e = below.select(e - I64x::splat(1), e);
+        let e = (self.0.simd_eq(F64x::splat(0.)) | self.0.is_nan())
+            .select(I64x::splat(i32::MIN as i64), e);
+        self.0
+            .is_infinite()
+            .select(I64x::splat(i32::MAX as i64), e)
+            .cast::<i32>()
+    }
 
-impl<const N: usize> AsDoubled for F64x<N> {
     #[inline]
-    fn as_doubled(self) -> Doubled<Self> {
-        Doubled::new(self, Self::splat(0.))
+    pub fn frexp(self) -> (Self, I32x<N>) {
+        let special = self.0.simd_eq(F64x::splat(0.)) | !self.0.is_finite();
+        let e = special.select(I32x::splat(0), self.ilogb() + I32x::splat(1));
+        (Self::select(special, self, self.ldexp(-e)), e)
     }
 }
 
-impl<const N: usize> MulAsDoubled for F64x<N> {
-    #[cfg(target_feature = "fma")]
+impl<const N: usize> core::ops::Add<Doubled<F64x<N>>> for F64x<N> {
+    type Output = Doubled<Self>;
     #[inline]
-    fn mul_as_doubled(self, other: Self) -> Doubled<Self> {
-        let r0 = self * other;
-        Doubled::new(r0, self.mul_add(other, -r0))
+    fn add(self, other: Doubled<Self>) -> Self::Output {
+        let r0 = self + other.0;
+        let v = r0 - self;
+        Doubled::new(r0, self - (r0 - v) + (other.0 - v) + other.1)
     }
-    #[cfg(not(target_feature = "fma"))]
+}
+
+impl<const N: usize> CheckOrder for F64x<N> {
     #[inline]
-    fn mul_as_doubled(self, other: Self) -> Doubled<Self> {
-        let xh = self.upper();
-        let xl = self - xh;
-        let yh = other.upper();
-        let yl = other - yh;
-        let r0 = self * other;
-        Doubled::new(r0, xh * yh + (-r0) + xl * yh + xh * yl + xl * yl)
+    fn check_order(self, other: Self) {
+        if cfg!(debug_assertions) {
+            let ok = !self.is_finite()
+                | !other.is_finite()
+                | self.abs().simd_ge(other.abs())
+                | ((self + other).abs().simd_le(self.abs())
+                    & (self + other).abs().simd_le(other.abs()));
+            if let Some(lane) = (!ok).first_set() {
+                panic!(
+                    "check_order failed in lane {lane}: |{}| < |{}|",
+                    self[lane], other[lane]
+                );
+            }
+        }
     }
 }
 
-impl<const N: usize> RecipAsDoubled for F64x<N> {
-    #[cfg(target_feature = "fma")]
-    #[inline]
-    fn recip_as_doubled(self) -> Doubled<Self> {
-        let q0 = self.recip();
-        Doubled::new(q0, q0 * (-self).mul_add(q0, Self::splat(1.)))
-    }
-    #[cfg(not(target_feature = "fma"))]
-    #[inline]
-    fn recip_as_doubled(self) -> Doubled<Self> {
-        let t = self.recip();
-        let dh = self.upper();
-        let dl = self - dh;
-        let th = t.upper();
-        let tl = t - th;
-        let q0 = t;
-        Doubled::new(
-            q0,
-            t * (Self::splat(1.) - dh * th - dh * tl - dl * th - dl * tl),
+impl<const N: usize> DoubledRandom for Doubled<F64x<N>> {
+    #[inline]
+    fn random<F: FnMut() -> u64>(next: &mut F) -> Self {
+        let words: [[u64; 2]; N] = core::array::from_fn(|_| [next(), next()]);
+        let hi = U64x::from_array(words.map(|w| w[0])) >> U64x::splat(11);
+        let lo = U64x::from_array(words.map(|w| w[1])) >> U64x::splat(11);
+        Self::new(
+            hi.cast::<f64>() * pow2k(I64x::splat(-53)),
+            lo.cast::<f64>() * pow2k(I64x::splat(-106)),
         )
+        .normalize()
     }
 }
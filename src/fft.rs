@@ -0,0 +1,338 @@
+//! Complex FFT in double-word arithmetic.
+//!
+//! A [`Plan`] holds the factorization of the length and the table of roots of
+//! unity, which are computed in double-word from an exact reduction of the
+//! angle rather than rounded from `f64`. The transforms are mixed-radix
+//! Stockham passes with radix 4, 2 and the odd prime factors of the length,
+//! vectorized over `Doubled<F64x<4>>` while the inner runs are long enough.
+//! Complex data is kept as separate slices of real and imaginary parts.
+//!
+//! A prime factor `p` costs `O(p²)` per pass, so lengths with large prime
+//! factors are slow.
+
+use crate::base::DoubledBase;
+use crate::complex::C;
+use crate::{Doubled, F64x, Normalize, LANES};
+
+type D = Doubled<f64>;
+
+/// `2π`.
+const TAU: D = Doubled::new(core::f64::consts::TAU, 2.4492935982947064e-16);
+
+/// Returns `(cos θ, sin θ)` for `θ = 2π k / n`, accurate to about the last bit
+/// of the double-word result.
+///
+/// The angle is reduced to `[0, π/4]` with integer arithmetic, so the
+/// symmetric roots are exactly symmetric and the ones on the axes are exact.
+///
+/// # Panics
+///
+/// Panics if `n == 0`.
+pub fn root_of_unity(k: usize, n: usize) -> (D, D) {
+    assert!(n > 0, "the order must not be zero");
+    // θ = q π/2 + 2π j / (4n) with j < n.
+    let k4 = 4 * (k % n) as u128;
+    let (q, j) = (k4 / n as u128, (k4 % n as u128) as usize);
+    let (c, s) = if 2 * j == n {
+        let h = D::from(0.5).sqrt().normalize();
+        (h, h)
+    } else if 2 * j < n {
+        sin_cos_reduced(j, n)
+    } else {
+        let (c, s) = sin_cos_reduced(n - j, n);
+        (s, c)
+    };
+    match q {
+        0 => (c, s),
+        1 => (-s, c),
+        2 => (-c, -s),
+        _ => (s, -c),
+    }
+}
+
+/// `(cos t, sin t)` of `t = 2π j / (4n)`, for `t < π/4`, by Taylor series.
+fn sin_cos_reduced(j: usize, n: usize) -> (D, D) {
+    let t = (TAU * D::from(j as f64) / D::from(4. * n as f64)).normalize();
+    let t2 = t.square();
+    let (mut c, mut s) = (D::from(1.), t);
+    let (mut tc, mut ts) = (c, s);
+    // The terms fall below 2^-110 after `t^30 / 30!`.
+    for i in 1..=15 {
+        let i = i as f64;
+        tc = -(tc * t2 / D::from((2. * i - 1.) * (2. * i))).normalize();
+        ts = -(ts * t2 / D::from(2. * i * (2. * i + 1.))).normalize();
+        c = (c + tc).normalize();
+        s = (s + ts).normalize();
+    }
+    (c, s)
+}
+
+/// Precomputed data for the transforms of one length.
+#[derive(Clone, Debug)]
+pub struct Plan {
+    n: usize,
+    factors: Vec<usize>,
+    /// `e^{-2πik/n}` for `k` in `0..n`, as real and imaginary parts.
+    re: Vec<D>,
+    im: Vec<D>,
+}
+
+impl Plan {
+    /// Creates a plan for transforms of length `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n == 0`.
+    pub fn new(n: usize) -> Self {
+        assert!(n > 0, "the length must not be zero");
+        let mut factors = Vec::new();
+        let mut m = n;
+        while m.is_multiple_of(4) {
+            factors.push(4);
+            m /= 4;
+        }
+        if m.is_multiple_of(2) {
+            factors.push(2);
+            m /= 2;
+        }
+        let mut p = 3;
+        while m > 1 {
+            if p * p > m {
+                p = m;
+            }
+            while m.is_multiple_of(p) {
+                factors.push(p);
+                m /= p;
+            }
+            p += 2;
+        }
+        let (re, im) = (0..n)
+            .map(|k| {
+                let (c, s) = root_of_unity(k, n);
+                (c, -s)
+            })
+            .unzip();
+        Self { n, factors, re, im }
+    }
+
+    /// Length of the transforms.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    /// Forward transform `X[f] = Σ x[t] e^{-2πi f t / n}` in place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `re` or `im` does not have [`Plan::size`] elements.
+    pub fn forward(&self, re: &mut [D], im: &mut [D]) {
+        self.transform::<false>(re, im);
+    }
+
+    /// Inverse transform `x[t] = Σ X[f] e^{2πi f t / n} / n` in place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `re` or `im` does not have [`Plan::size`] elements.
+    pub fn inverse(&self, re: &mut [D], im: &mut [D]) {
+        self.transform::<true>(re, im);
+        let n = D::from(self.n as f64);
+        for x in re.iter_mut().chain(im.iter_mut()) {
+            *x = (*x / n).normalize();
+        }
+    }
+
+    /// `e^{∓2πik/n}`, conjugated for the inverse transform.
+    #[inline]
+    fn root<const INV: bool>(&self, k: usize) -> C<f64> {
+        let im = self.im[k];
+        C {
+            re: self.re[k],
+            im: if INV { -im } else { im },
+        }
+    }
+
+    fn transform<const INV: bool>(&self, re: &mut [D], im: &mut [D]) {
+        assert_eq!(re.len(), self.n);
+        assert_eq!(im.len(), self.n);
+        let mut src = (re.to_vec(), im.to_vec());
+        let mut dst = (src.0.clone(), src.1.clone());
+        let mut l = 1;
+        for &p in &self.factors {
+            self.pass::<INV>(p, l, (&src.0, &src.1), (&mut dst.0, &mut dst.1));
+            core::mem::swap(&mut src, &mut dst);
+            l *= p;
+        }
+        re.copy_from_slice(&src.0);
+        im.copy_from_slice(&src.1);
+    }
+
+    /// One radix-`p` pass after the sub-transforms of length `l`.
+    ///
+    /// With `r = n / (l p)`, the input holds at `j p r + t r + k` the length-`l`
+    /// transform at frequency `j` of the subsequence `t r + k` of stride
+    /// `p r`. The output holds at `(j + l s) r + k` the length-`l p` transform
+    /// at frequency `j + l s` of the subsequence `k` of stride `r`.
+    fn pass<const INV: bool>(
+        &self,
+        p: usize,
+        l: usize,
+        src: (&[D], &[D]),
+        dst: (&mut [D], &mut [D]),
+    ) {
+        let r = self.n / (l * p);
+        let mut twiddles = vec![C::<f64>::ZERO; p];
+        let mut scalar = vec![C::<f64>::ZERO; 2 * p];
+        let mut vector = vec![C::<F64x>::ZERO; 2 * p];
+        let (dst_re, dst_im) = dst;
+        for j in 0..l {
+            for (t, w) in twiddles.iter_mut().enumerate() {
+                *w = self.root::<INV>(j * t * r);
+            }
+            let mut k = 0;
+            while k + LANES <= r {
+                self.butterfly::<INV, _>(
+                    p,
+                    l,
+                    r,
+                    j,
+                    k,
+                    &twiddles,
+                    src,
+                    (dst_re, dst_im),
+                    &mut vector,
+                );
+                k += LANES;
+            }
+            for k in k..r {
+                self.butterfly::<INV, _>(
+                    p,
+                    l,
+                    r,
+                    j,
+                    k,
+                    &twiddles,
+                    src,
+                    (dst_re, dst_im),
+                    &mut scalar,
+                );
+            }
+        }
+    }
+
+    /// The radix-`p` butterfly of [`Plan::pass`] at `j` and `k`, on a lane of
+    /// `T` starting there.
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    fn butterfly<const INV: bool, T: Lanes>(
+        &self,
+        p: usize,
+        l: usize,
+        r: usize,
+        j: usize,
+        k: usize,
+        twiddles: &[C<f64>],
+        src: (&[D], &[D]),
+        dst: (&mut [D], &mut [D]),
+        buf: &mut [C<T>],
+    ) {
+        let (b, out) = buf.split_at_mut(p);
+        for (t, (b, &w)) in b.iter_mut().zip(twiddles).enumerate() {
+            let i = j * p * r + t * r + k;
+            let x = C {
+                re: T::load(&src.0[i..]),
+                im: T::load(&src.1[i..]),
+            };
+            *b = if t == 0 {
+                x
+            } else {
+                x.mul(T::broadcast_complex(w))
+            };
+        }
+        match p {
+            2 => {
+                out[0] = b[0].add(b[1]);
+                out[1] = b[0].sub(b[1]);
+            }
+            4 => {
+                let (a0, a1) = (b[0].add(b[2]), b[0].sub(b[2]));
+                let (a2, a3) = (b[1].add(b[3]), b[1].sub(b[3]));
+                // `-i a3` forward, `i a3` inverse.
+                let a3 = if INV { a3.mul_i() } else { a3.mul_i().neg() };
+                out[0] = a0.add(a2);
+                out[1] = a1.add(a3);
+                out[2] = a0.sub(a2);
+                out[3] = a1.sub(a3);
+            }
+            _ => {
+                let step = self.n / p;
+                for (s, out) in out.iter_mut().enumerate() {
+                    let mut acc = b[0];
+                    for (t, &b) in b.iter().enumerate().skip(1) {
+                        acc = acc
+                            .add(b.mul(T::broadcast_complex(self.root::<INV>((s * t) % p * step))));
+                    }
+                    *out = acc;
+                }
+            }
+        }
+        for (s, out) in out.iter().enumerate() {
+            let i = (j + l * s) * r + k;
+            T::store(out.re, &mut dst.0[i..]);
+            T::store(out.im, &mut dst.1[i..]);
+        }
+    }
+}
+
+/// Scalar and vector element types of the passes.
+trait Lanes: DoubledBase {
+    fn load(xs: &[D]) -> Doubled<Self>;
+    fn store(x: Doubled<Self>, out: &mut [D]);
+    fn broadcast(x: D) -> Doubled<Self>;
+
+    #[inline(always)]
+    fn broadcast_complex(x: C<f64>) -> C<Self> {
+        C {
+            re: Self::broadcast(x.re),
+            im: Self::broadcast(x.im),
+        }
+    }
+}
+
+impl Lanes for f64 {
+    #[inline(always)]
+    fn load(xs: &[D]) -> D {
+        xs[0]
+    }
+    #[inline(always)]
+    fn store(x: D, out: &mut [D]) {
+        out[0] = x;
+    }
+    #[inline(always)]
+    fn broadcast(x: D) -> D {
+        x
+    }
+}
+
+impl Lanes for F64x {
+    #[inline(always)]
+    fn load(xs: &[D]) -> Doubled<F64x> {
+        Doubled::<F64x>::load_interleaved(xs)
+    }
+    #[inline(always)]
+    fn store(x: Doubled<F64x>, out: &mut [D]) {
+        x.store_interleaved(out)
+    }
+    #[inline(always)]
+    fn broadcast(x: D) -> Doubled<F64x> {
+        Doubled::<F64x>::splat(x)
+    }
+}
+
+impl C<F64x> {
+    const ZERO: Self = Self {
+        re: Doubled::<F64x>::splat(Doubled::new(0., 0.)),
+        im: Doubled::<F64x>::splat(Doubled::new(0., 0.)),
+    };
+}
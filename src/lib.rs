@@ -1,10 +1,28 @@
 #![deny(warnings)]
 #![allow(clippy::wrong_self_convention)]
-#![cfg_attr(not(feature = "simd"), no_std)]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![cfg_attr(feature = "simd", feature(portable_simd))]
 mod f32;
 mod f64;
 
+pub mod base;
+#[cfg(feature = "std")]
+mod complex;
+pub mod eft;
+pub mod linalg;
+#[cfg(feature = "std")]
+pub mod ode;
+#[cfg(feature = "std")]
+pub mod poly;
+#[cfg(feature = "std")]
+pub mod quad;
+#[cfg(feature = "std")]
+pub mod remez;
+pub mod stats;
+
+#[cfg(feature = "verify")]
+pub mod verify;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Doubled<T>(pub T, pub T);
 
@@ -77,6 +95,41 @@ pub trait RecipAsDoubled: Sized {
     fn recip_as_doubled(self) -> Doubled<Self>;
 }
 
+/// Variants of the multiplicative operations that are accurate over the whole
+/// exponent range.
+///
+/// The operands are scaled by powers of two to exponent zero before the usual
+/// kernels run, and the result is scaled back with the double-word `ldexp`,
+/// so that a subnormal result is rounded once. The plain operations overflow
+/// in intermediate terms near the largest exponents and lose the low word
+/// near the subnormal range.
+pub trait FullRange: Sized {
+    fn mul_full_range(self, other: Self) -> Self;
+    fn div_full_range(self, other: Self) -> Self;
+    fn recip_full_range(self) -> Self;
+    fn square_full_range(self) -> Self;
+}
+
+/// [`MulAsDoubled`] accurate over the whole exponent range, see [`FullRange`].
+pub trait MulAsDoubledFullRange: Sized {
+    fn mul_as_doubled_full_range(self, other: Self) -> Doubled<Self>;
+}
+
+/// Uniformly distributed random values in `[0, 1)`, drawn on the grid of the
+/// full double-word significand: multiples of `2^-106` for `f64` and of
+/// `2^-48` for `f32`.
+///
+/// Every grid point is drawn with the same probability. The high word is in
+/// `[0, 1)`, except for values that round to one, which have a high word of
+/// one and a negative low word.
+///
+/// The bits come from the caller, so any generator can be plugged in and the
+/// results are reproducible. SIMD values consume the bits lane by lane, so lane
+/// `i` equals the `i`-th of `N` successive scalar draws.
+pub trait DoubledRandom: Sized {
+    fn random<F: FnMut() -> u64>(next: &mut F) -> Self;
+}
+
 pub trait AddChecked<T = Self>: CheckOrder<T> {
     type Output;
     fn add_checked(self, other: T) -> Self::Output;
@@ -305,3 +358,18 @@ pub trait Upper: Sized {
 mod f32x;
 #[cfg(feature = "simd")]
 mod f64x;
+
+/// Number of `f64` lanes of the vectors the slice kernels, the FFT and the
+/// statistics run on.
+#[cfg(feature = "simd")]
+pub(crate) const LANES: usize = 4;
+#[cfg(feature = "simd")]
+pub(crate) type F64x = core::simd::Simd<f64, LANES>;
+/// The `f32` vector of the same width as [`F64x`].
+#[cfg(feature = "simd")]
+pub(crate) type F32x = core::simd::Simd<f32, { 2 * LANES }>;
+
+#[cfg(feature = "simd")]
+pub mod fft;
+#[cfg(feature = "simd")]
+pub mod slice;
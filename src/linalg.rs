@@ -0,0 +1,297 @@
+//! Dense linear solvers in double-word arithmetic.
+//!
+//! Matrices are row-major slices of [`Doubled<f64>`] and are factored in place,
+//! so nothing is allocated: the caller provides the matrix, the right-hand
+//! sides and the small pivot or scalar-factor arrays. Only `refine`, which
+//! needs the `std` feature, returns a fresh `Vec`; [`refine_into`] writes into
+//! caller slices instead. All arithmetic goes through the double-word
+//! operations of the crate, which gives about 106 bits of working precision
+//! and lets systems that are too ill-conditioned for `f64` still be solved
+//! accurately. Every stored or accumulated value is renormalized, as in the
+//! reductions, so that the low words do not grow over the long update chains.
+//!
+//! # Panics
+//!
+//! The functions panic if a slice is shorter than the dimensions require.
+
+use crate::{Doubled, MulAsDoubled, Normalize};
+
+type D = Doubled<f64>;
+
+#[cfg(feature = "std")]
+const ZERO: D = Doubled::new(0., 0.);
+
+/// Error of a factorization or of a solve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A pivot or a diagonal entry of a triangular factor is zero.
+    Singular,
+    /// A diagonal entry of the Cholesky factor would be the square root of a
+    /// non-positive number.
+    NotPositiveDefinite,
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Singular => "matrix is singular",
+            Self::NotPositiveDefinite => "matrix is not positive definite",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Magnitude of `x` as a pair, for comparisons of the exact values.
+#[inline]
+fn magnitude(x: D) -> (f64, f64) {
+    let a = x.abs();
+    (a.0, a.1)
+}
+
+/// Factors the `n × n` matrix `a` as `P A = L U` with partial pivoting.
+///
+/// On return the strict lower triangle of `a` holds `L`, whose diagonal is
+/// implicitly one, and the upper triangle holds `U`. At step `k`, row `k` was
+/// swapped with row `piv[k]`.
+pub fn lu_factor(a: &mut [D], n: usize, piv: &mut [usize]) -> Result<(), Error> {
+    assert!(a.len() >= n * n && piv.len() >= n);
+    for k in 0..n {
+        let p = (k..n)
+            .max_by(|&i, &j| {
+                magnitude(a[i * n + k])
+                    .partial_cmp(&magnitude(a[j * n + k]))
+                    .unwrap_or(core::cmp::Ordering::Equal)
+            })
+            .unwrap_or(k);
+        piv[k] = p;
+        if a[p * n + k].0 == 0. {
+            return Err(Error::Singular);
+        }
+        if p != k {
+            for j in 0..n {
+                a.swap(k * n + j, p * n + j);
+            }
+        }
+        let pivot = a[k * n + k];
+        for i in k + 1..n {
+            let l = (a[i * n + k] / pivot).normalize();
+            a[i * n + k] = l;
+            for j in k + 1..n {
+                a[i * n + j] = (a[i * n + j] - l * a[k * n + j]).normalize();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Solves `A x = b` in place, given the factorization from [`lu_factor`].
+pub fn lu_solve(lu: &[D], n: usize, piv: &[usize], b: &mut [D]) {
+    assert!(lu.len() >= n * n && piv.len() >= n && b.len() >= n);
+    for (k, &p) in piv[..n].iter().enumerate() {
+        b.swap(k, p);
+    }
+    for i in 0..n {
+        let mut s = b[i];
+        for j in 0..i {
+            s = (s - lu[i * n + j] * b[j]).normalize();
+        }
+        b[i] = s;
+    }
+    back_substitute(lu, n, b);
+}
+
+/// Solves `R x = b` in place for the upper triangle `R` of the first `n` rows
+/// of the `n`-column matrix `r`.
+fn back_substitute(r: &[D], n: usize, b: &mut [D]) {
+    for i in (0..n).rev() {
+        let mut s = b[i];
+        for j in i + 1..n {
+            s = (s - r[i * n + j] * b[j]).normalize();
+        }
+        b[i] = (s / r[i * n + i]).normalize();
+    }
+}
+
+/// Factors the symmetric positive definite `n × n` matrix `a` as `A = L Lᵀ`.
+///
+/// Only the lower triangle of `a` is read, and it is overwritten by `L`. The
+/// strict upper triangle is left untouched.
+#[cfg(feature = "std")]
+pub fn cholesky_factor(a: &mut [D], n: usize) -> Result<(), Error> {
+    assert!(a.len() >= n * n);
+    for j in 0..n {
+        let mut d = a[j * n + j];
+        for k in 0..j {
+            d = (d - a[j * n + k].square()).normalize();
+        }
+        if d.0 <= 0. || d.0.is_nan() {
+            return Err(Error::NotPositiveDefinite);
+        }
+        let l = d.sqrt().normalize();
+        a[j * n + j] = l;
+        for i in j + 1..n {
+            let mut s = a[i * n + j];
+            for k in 0..j {
+                s = (s - a[i * n + k] * a[j * n + k]).normalize();
+            }
+            a[i * n + j] = (s / l).normalize();
+        }
+    }
+    Ok(())
+}
+
+/// Solves `A x = b` in place, given the factor from [`cholesky_factor`].
+#[cfg(feature = "std")]
+pub fn cholesky_solve(l: &[D], n: usize, b: &mut [D]) {
+    assert!(l.len() >= n * n && b.len() >= n);
+    for i in 0..n {
+        let mut s = b[i];
+        for k in 0..i {
+            s = (s - l[i * n + k] * b[k]).normalize();
+        }
+        b[i] = (s / l[i * n + i]).normalize();
+    }
+    for i in (0..n).rev() {
+        let mut s = b[i];
+        for k in i + 1..n {
+            s = (s - l[k * n + i] * b[k]).normalize();
+        }
+        b[i] = (s / l[i * n + i]).normalize();
+    }
+}
+
+/// Factors the `m × n` matrix `a`, with `m >= n`, as `A = Q R` by Householder
+/// reflections.
+///
+/// On return the upper triangle of `a` holds `R`. Below the diagonal, column
+/// `k` holds the reflector `v` of step `k`, whose leading one is implicit, and
+/// `tau[k]` its factor, so that `H_k = I - tau[k] v vᵀ` and `Q = H_0 ⋯ H_{n-1}`.
+#[cfg(feature = "std")]
+pub fn qr_factor(a: &mut [D], m: usize, n: usize, tau: &mut [D]) {
+    assert!(m >= n && a.len() >= m * n && tau.len() >= n);
+    for k in 0..n {
+        let mut norm2 = ZERO;
+        for i in k + 1..m {
+            norm2 = (norm2 + a[i * n + k].square()).normalize();
+        }
+        if norm2.0 == 0. {
+            tau[k] = ZERO;
+            continue;
+        }
+        let alpha = a[k * n + k];
+        let norm = (alpha.square() + norm2).normalize().sqrt().normalize();
+        let beta = if alpha.0 < 0. { norm } else { -norm };
+        tau[k] = ((beta - alpha) / beta).normalize();
+        let scale = (alpha - beta).normalize().recip();
+        for i in k + 1..m {
+            a[i * n + k] = (a[i * n + k] * scale).normalize();
+        }
+        a[k * n + k] = beta;
+        for j in k + 1..n {
+            let mut w = a[k * n + j];
+            for i in k + 1..m {
+                w = (w + a[i * n + k] * a[i * n + j]).normalize();
+            }
+            let w = w * tau[k];
+            a[k * n + j] = (a[k * n + j] - w).normalize();
+            for i in k + 1..m {
+                a[i * n + j] = (a[i * n + j] - w * a[i * n + k]).normalize();
+            }
+        }
+    }
+}
+
+/// Solves the least-squares problem `min |A x - b|` in place, given the
+/// factorization from [`qr_factor`]. For square `A` this is `A x = b`.
+///
+/// `b` has `m` entries. On return the first `n` hold `x`, and the norm of the
+/// remaining ones is the residual norm.
+#[cfg(feature = "std")]
+pub fn qr_solve(qr: &[D], m: usize, n: usize, tau: &[D], b: &mut [D]) -> Result<(), Error> {
+    assert!(m >= n && qr.len() >= m * n && tau.len() >= n && b.len() >= m);
+    for k in 0..n {
+        let mut w = b[k];
+        for i in k + 1..m {
+            w = (w + qr[i * n + k] * b[i]).normalize();
+        }
+        let w = w * tau[k];
+        b[k] = (b[k] - w).normalize();
+        for i in k + 1..m {
+            b[i] = (b[i] - w * qr[i * n + k]).normalize();
+        }
+    }
+    if (0..n).any(|i| qr[i * n + i].0 == 0.) {
+        return Err(Error::Singular);
+    }
+    back_substitute(qr, n, b);
+    Ok(())
+}
+
+/// Refines the solution of the `f64` system `A x = b` to double-word accuracy
+/// by mixed-precision iterative refinement.
+///
+/// `solve` solves `A d = r` in place with a factorization of `A` computed by
+/// the caller in `f64`, for instance by LAPACK. It gives the starting solution,
+/// then each step computes the residual `r = b - A x` in double-word
+/// arithmetic, from the exact products [`MulAsDoubled`] and the accurate sum,
+/// rounds it to `f64`, and adds the correction `d` to `x`. The
+/// loop stops after `iterations` steps or once a correction no longer changes
+/// `x`, and the number of steps taken is returned.
+///
+/// `a` is row-major `n × n`, and `x` and the scratch `work` need `n` entries.
+/// The iteration converges when `A` is not too ill-conditioned for the `f64`
+/// factorization, that is when its condition number is well below `2^53`.
+pub fn refine_into<S>(
+    a: &[f64],
+    n: usize,
+    b: &[f64],
+    mut solve: S,
+    iterations: usize,
+    x: &mut [D],
+    work: &mut [f64],
+) -> usize
+where
+    S: FnMut(&mut [f64]),
+{
+    assert!(a.len() >= n * n && b.len() >= n && x.len() >= n && work.len() >= n);
+    let (x, work) = (&mut x[..n], &mut work[..n]);
+    work.copy_from_slice(&b[..n]);
+    solve(work);
+    for (x, &w) in x.iter_mut().zip(work.iter()) {
+        *x = Doubled::from(w);
+    }
+    for step in 0..iterations {
+        for (i, w) in work.iter_mut().enumerate() {
+            let mut r = Doubled::from(b[i]);
+            for (&a, &x) in a[i * n..(i + 1) * n].iter().zip(x.iter()) {
+                r = (r - a.mul_as_doubled(x.0) + -(a * x.1)).normalize();
+            }
+            *w = r.0 + r.1;
+        }
+        solve(work);
+        let mut changed = false;
+        for (x, &d) in x.iter_mut().zip(work.iter()) {
+            let y = (*x + d).normalize();
+            changed |= y != *x;
+            *x = y;
+        }
+        if !changed {
+            return step + 1;
+        }
+    }
+    iterations
+}
+
+/// [`refine_into`] returning the solution as a new vector.
+#[cfg(feature = "std")]
+pub fn refine<S>(a: &[f64], n: usize, b: &[f64], solve: S, iterations: usize) -> Vec<D>
+where
+    S: FnMut(&mut [f64]),
+{
+    let mut x = vec![Doubled::new(0., 0.); n];
+    let mut work = vec![0.; n];
+    refine_into(a, n, b, solve, iterations, &mut x, &mut work);
+    x
+}
@@ -0,0 +1,203 @@
+//! Roots of real and complex polynomials in double-word arithmetic.
+//!
+//! The roots are found all at once by the Aberth–Ehrlich iteration, a Newton
+//! step corrected for the other approximations. Every step runs in complex
+//! double-word arithmetic with the polynomial and its derivative evaluated by
+//! Horner's scheme, so roots that are too close to be told apart in `f64`,
+//! down to a separation of about `1e-8` for a cluster of three, still come out
+//! accurate.
+//!
+//! Coefficients are given constant term first, as for `slice::polyval`, either
+//! as `f64` or as [`Doubled<f64>`].
+
+use crate::{Doubled, Normalize};
+
+type D = Doubled<f64>;
+type C = crate::complex::C<f64>;
+
+const ZERO: D = Doubled::new(0., 0.);
+
+/// Relative precision of double-word values, `2^-104`.
+const EPS: f64 = 1. / (1u128 << 104) as f64;
+
+const MAX_ITERATIONS: usize = 500;
+
+/// A root `re + i im` with a bound of its error.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Root {
+    pub re: D,
+    pub im: D,
+    /// Radius of a disk around the root that contains an exact root of the
+    /// polynomial. The disks are the inclusion disks of Braess and Hadeler,
+    /// widened by a bound of the rounding errors of the evaluation, and are
+    /// only meaningful for roots whose disks do not overlap.
+    pub error: f64,
+}
+
+/// Roots of the real polynomial `coeffs[0] + coeffs[1] x + ...`, sorted by real
+/// and then imaginary part. Roots at zero are split off exactly, with a zero
+/// error bound, and sorted with the others.
+///
+/// Leading zero coefficients are ignored, so a polynomial of degree `n` has `n`
+/// roots, counted with multiplicity. The zero polynomial has none.
+pub fn roots<T: Copy + Into<D>>(coeffs: &[T]) -> Vec<Root> {
+    solve(
+        coeffs
+            .iter()
+            .map(|&c| C {
+                re: c.into(),
+                im: ZERO,
+            })
+            .collect(),
+    )
+}
+
+/// Roots of the complex polynomial with coefficients `re[i] + i im[i]`, as
+/// [`roots`].
+///
+/// # Panics
+///
+/// Panics if `re` and `im` have different lengths.
+pub fn roots_complex<T: Copy + Into<D>>(re: &[T], im: &[T]) -> Vec<Root> {
+    assert_eq!(re.len(), im.len());
+    solve(
+        re.iter()
+            .zip(im)
+            .map(|(&re, &im)| C {
+                re: re.into(),
+                im: im.into(),
+            })
+            .collect(),
+    )
+}
+
+fn solve(mut a: Vec<C>) -> Vec<Root> {
+    while a.last().is_some_and(|c| c.is_zero()) {
+        a.pop();
+    }
+    let zeros = a.iter().take_while(|c| c.is_zero()).count();
+    a.drain(..zeros);
+    let mut roots = vec![
+        Root {
+            re: ZERO,
+            im: ZERO,
+            error: 0.,
+        };
+        zeros
+    ];
+    let n = a.len().saturating_sub(1);
+    if n == 0 {
+        return roots;
+    }
+
+    // Start on a circle whose radius bounds the roots, away from the axes so
+    // that real polynomials do not start on a symmetric set.
+    let mags: Vec<f64> = a.iter().map(|c| c.magnitude()).collect();
+    let radius = (0..n)
+        .map(|i| (mags[i] / mags[n]).powf(1. / (n - i) as f64))
+        .fold(0., f64::max);
+    let mut z: Vec<C> = (0..n)
+        .map(|k| {
+            let t = core::f64::consts::TAU * k as f64 / n as f64 + 0.4;
+            C {
+                re: D::from(radius * t.cos()),
+                im: D::from(radius * t.sin()),
+            }
+        })
+        .collect();
+
+    let mut converged = vec![false; n];
+    for _ in 0..MAX_ITERATIONS {
+        if converged.iter().all(|&c| c) {
+            break;
+        }
+        for k in 0..n {
+            if converged[k] {
+                continue;
+            }
+            let (p, dp) = horner(&a, z[k]);
+            if p.magnitude() <= rounding_bound(&mags, z[k]) || dp.is_zero() {
+                converged[k] = true;
+                continue;
+            }
+            let ratio = p.div(dp);
+            let mut s = C::ZERO;
+            for (j, &zj) in z.iter().enumerate() {
+                if j != k {
+                    s = s.add(z[k].sub(zj).recip());
+                }
+            }
+            let w = ratio.div(C::ONE.sub(ratio.mul(s)));
+            z[k] = z[k].sub(w);
+            converged[k] = w.magnitude() <= 4. * EPS * z[k].magnitude();
+        }
+    }
+
+    for (k, &zk) in z.iter().enumerate() {
+        let (p, _) = horner(&a, zk);
+        let distance: f64 = z
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != k)
+            .map(|(_, &zj)| zk.sub(zj).magnitude())
+            .product();
+        let residual = p.magnitude() + rounding_bound(&mags, zk);
+        roots.push(Root {
+            re: zk.re,
+            im: zk.im,
+            error: n as f64 * residual / (mags[n] * distance),
+        });
+    }
+    roots.sort_by(|x, y| {
+        (x.re.0, x.re.1, x.im.0, x.im.1)
+            .partial_cmp(&(y.re.0, y.re.1, y.im.0, y.im.1))
+            .unwrap_or(core::cmp::Ordering::Equal)
+    });
+    roots
+}
+
+/// Values of the polynomial and of its derivative at `z`.
+fn horner(a: &[C], z: C) -> (C, C) {
+    let mut p = C::ZERO;
+    let mut dp = C::ZERO;
+    for &c in a.iter().rev() {
+        dp = dp.mul(z).add(p);
+        p = p.mul(z).add(c);
+    }
+    (p, dp)
+}
+
+/// Bound of the rounding error of [`horner`] at `z`.
+fn rounding_bound(mags: &[f64], z: C) -> f64 {
+    let r = z.magnitude();
+    let sum = mags.iter().rev().fold(0., |s, &m| s * r + m);
+    4. * mags.len() as f64 * EPS * sum
+}
+
+impl C {
+    const ONE: Self = Self {
+        re: Doubled::new(1., 0.),
+        im: ZERO,
+    };
+
+    fn is_zero(self) -> bool {
+        self.re.0 == 0. && self.im.0 == 0.
+    }
+
+    /// Modulus rounded to `f64`.
+    fn magnitude(self) -> f64 {
+        (self.re.0 + self.re.1).hypot(self.im.0 + self.im.1)
+    }
+
+    fn recip(self) -> Self {
+        let d = (self.re.square() + self.im.square()).normalize();
+        Self {
+            re: (self.re / d).normalize(),
+            im: (-self.im / d).normalize(),
+        }
+    }
+
+    fn div(self, other: Self) -> Self {
+        self.mul(other.recip())
+    }
+}
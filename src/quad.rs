@@ -0,0 +1,275 @@
+//! Gauss–Legendre and Gauss–Kronrod quadrature in double-word arithmetic.
+//!
+//! The Gauss nodes are found by Newton's method on the three-term recurrence
+//! of the Legendre polynomials, run in [`Doubled<f64>`], so the nodes and
+//! weights rounded to `f64` are correctly rounded up to ties. The Kronrod
+//! nodes are the roots of the Stieltjes polynomial, whose Legendre coefficients
+//! come from its orthogonality conditions, and the Kronrod weights make the
+//! rule exact for polynomials of degree up to `3n + 1`.
+
+use crate::linalg::{self, Error};
+use crate::{Doubled, Normalize};
+
+type D = Doubled<f64>;
+
+const ZERO: D = Doubled::new(0., 0.);
+const ONE: D = Doubled::new(1., 0.);
+
+const MAX_ITERATIONS: usize = 100;
+
+/// Relative precision of double-word values, `2^-104`.
+const EPS: f64 = 1. / (1u128 << 104) as f64;
+
+/// Quadrature rule on `[-1, 1]`, with nodes in increasing order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub nodes: Vec<D>,
+    pub weights: Vec<D>,
+}
+
+impl Rule {
+    /// Nodes rounded to `f64`.
+    pub fn nodes_f64(&self) -> Vec<f64> {
+        self.nodes.iter().map(|x| x.0 + x.1).collect()
+    }
+
+    /// Weights rounded to `f64`.
+    pub fn weights_f64(&self) -> Vec<f64> {
+        self.weights.iter().map(|w| w.0 + w.1).collect()
+    }
+
+    /// `∫ f` over `[a, b]`, with the sum `Σ w_i f(x_i)` accumulated in
+    /// double-word.
+    pub fn integrate<F: FnMut(D) -> D>(&self, a: f64, b: f64, mut f: F) -> D {
+        let (mid, half) = interval(a, b);
+        let s = self
+            .nodes
+            .iter()
+            .zip(&self.weights)
+            .fold(ZERO, |s, (&x, &w)| (s + w * f(mid + half * x)).normalize());
+        (s * half).normalize()
+    }
+}
+
+/// Gauss–Kronrod pair: the `2n + 1`-point Kronrod extension of the `n`-point
+/// Gauss rule.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GaussKronrod {
+    /// The Kronrod rule. Its nodes of odd index are the Gauss nodes.
+    pub kronrod: Rule,
+    /// Weight of the Gauss rule at `kronrod.nodes[2 * i + 1]`.
+    pub gauss_weights: Vec<D>,
+}
+
+impl GaussKronrod {
+    /// `∫ f` over `[a, b]` by the Kronrod rule, and the difference from the
+    /// Gauss rule as an error estimate. The Gauss sum reuses the values of `f`.
+    pub fn integrate<F: FnMut(D) -> D>(&self, a: f64, b: f64, mut f: F) -> (D, f64) {
+        let (mid, half) = interval(a, b);
+        let (mut k, mut g) = (ZERO, ZERO);
+        for (i, (&x, &w)) in self
+            .kronrod
+            .nodes
+            .iter()
+            .zip(&self.kronrod.weights)
+            .enumerate()
+        {
+            let y = f(mid + half * x);
+            k = (k + w * y).normalize();
+            if i % 2 == 1 {
+                g = (g + self.gauss_weights[i / 2] * y).normalize();
+            }
+        }
+        let (k, g) = ((k * half).normalize(), (g * half).normalize());
+        let e = (k - g).normalize();
+        (k, (e.0 + e.1).abs())
+    }
+}
+
+/// Midpoint and half-length of `[a, b]`.
+fn interval(a: f64, b: f64) -> (D, D) {
+    let (a, b) = (D::from(a), D::from(b));
+    (((a + b) * 0.5).normalize(), ((b - a) * 0.5).normalize())
+}
+
+/// Calls `visit(j, P_j(x), P_j'(x))` for `j` in `0..=n`.
+fn legendre<V: FnMut(usize, D, D)>(n: usize, x: D, mut visit: V) {
+    let (mut p0, mut p1) = (ZERO, ONE);
+    let (mut d0, mut d1) = (ZERO, ZERO);
+    visit(0, p1, d1);
+    for k in 0..n {
+        // P_{k+1} = ((2k + 1) x P_k - k P_{k-1}) / (k + 1)
+        // P'_{k+1} = P'_{k-1} + (2k + 1) P_k
+        let kf = k as f64;
+        // Without the intermediate renormalizations the sum cancels to zero
+        // well before the roots are reached.
+        let xp = ((x * p1).normalize() * (2. * kf + 1.)).normalize();
+        let p2 = ((xp - p0 * kf).normalize() / D::from(kf + 1.)).normalize();
+        let d2 = if k == 0 {
+            ONE
+        } else {
+            (d0 + p1 * (2. * kf + 1.)).normalize()
+        };
+        (p0, p1, d0, d1) = (p1, p2, d1, d2);
+        visit(k + 1, p1, d1);
+    }
+}
+
+/// `(P_n(x), P_n'(x))`.
+fn legendre_n(n: usize, x: D) -> (D, D) {
+    let mut r = (ONE, ZERO);
+    legendre(n, x, |j, p, d| {
+        if j == n {
+            r = (p, d);
+        }
+    });
+    r
+}
+
+/// The `n`-point Gauss–Legendre rule, exact for polynomials of degree up to
+/// `2n - 1`.
+pub fn gauss_legendre(n: usize) -> Rule {
+    let mut nodes = vec![ZERO; n];
+    let mut weights = vec![ZERO; n];
+    // Nodes from the largest down, mirrored; the middle node of odd `n` is 0.
+    for i in 0..n.div_ceil(2) {
+        let mut x = if 2 * i + 1 == n {
+            ZERO
+        } else {
+            D::from((core::f64::consts::PI * (i as f64 + 0.75) / (n as f64 + 0.5)).cos())
+        };
+        if 2 * i + 1 != n {
+            // Newton's method converges quadratically, so once a step no
+            // longer shrinks it is rounding noise and is not taken.
+            let mut last = f64::INFINITY;
+            for _ in 0..MAX_ITERATIONS {
+                let (p, d) = legendre_n(n, x);
+                let dx = (p / d).normalize();
+                if dx.0.abs() >= last {
+                    break;
+                }
+                x = (x - dx).normalize();
+                if dx.0.abs() <= 4. * EPS * x.0.abs() {
+                    break;
+                }
+                last = dx.0.abs();
+            }
+        }
+        let (_, d) = legendre_n(n, x);
+        let q = ((ONE - x).normalize() * (ONE + x).normalize()).normalize();
+        let w = (D::from(2.) / (q * d.square().normalize()).normalize()).normalize();
+        nodes[n - 1 - i] = x;
+        nodes[i] = -x;
+        weights[n - 1 - i] = w;
+        weights[i] = w;
+    }
+    Rule { nodes, weights }
+}
+
+/// The `2n + 1`-point Gauss–Kronrod rule extending the `n`-point Gauss rule,
+/// exact for polynomials of degree up to `3n + 1`.
+///
+/// The Kronrod weights solve a moment system of size `2n + 1`, so the cost
+/// grows as `n³`. An error is returned if that system is singular, which does
+/// not happen for the orders in practical use.
+///
+/// # Panics
+///
+/// Panics if `n == 0`.
+pub fn gauss_kronrod(n: usize) -> Result<GaussKronrod, Error> {
+    assert!(n > 0, "the Gauss rule must have a node");
+    let gauss = gauss_legendre(n);
+
+    // E = P_{n+1} + Σ c_j P_j over j < n + 1 of the parity of n + 1, with
+    // ∫ P_n E P_k = 0 for the odd k <= n. The integrands have degree at most
+    // 3n + 1, so a Gauss rule of (3n + 2) / 2 nodes integrates them exactly.
+    let js: Vec<usize> = (0..n).rev().step_by(2).collect();
+    let ks: Vec<usize> = (1..=n).step_by(2).collect();
+    let m = js.len();
+    let exact = gauss_legendre((3 * n + 3) / 2);
+    let mut a = vec![ZERO; m * m];
+    let mut c = vec![ZERO; m];
+    let mut p = vec![ZERO; n + 2];
+    for (&x, &w) in exact.nodes.iter().zip(&exact.weights) {
+        legendre(n + 1, x, |j, pj, _| p[j] = pj);
+        let wp = (w * p[n]).normalize();
+        for (r, &k) in ks.iter().enumerate() {
+            let wpk = (wp * p[k]).normalize();
+            for (col, &j) in js.iter().enumerate() {
+                a[r * m + col] = (a[r * m + col] + wpk * p[j]).normalize();
+            }
+            c[r] = (c[r] - wpk * p[n + 1]).normalize();
+        }
+    }
+    let mut piv = vec![0; m];
+    linalg::lu_factor(&mut a, m, &mut piv)?;
+    linalg::lu_solve(&a, m, &piv, &mut c);
+    let stieltjes = |x: D| {
+        let (mut e, mut de) = (ZERO, ZERO);
+        legendre(n + 1, x, |j, pj, dj| {
+            let cj = if j == n + 1 {
+                Some(ONE)
+            } else {
+                js.iter().position(|&i| i == j).map(|i| c[i])
+            };
+            if let Some(cj) = cj {
+                e = (e + cj * pj).normalize();
+                de = (de + cj * dj).normalize();
+            }
+        });
+        (e, de)
+    };
+
+    // The roots of E interlace with the Gauss nodes. Each is bracketed in
+    // `f64` by bisection, then polished by Newton steps in double-word.
+    let mut nodes = vec![ZERO; 2 * n + 1];
+    for i in 0..n {
+        nodes[2 * i + 1] = gauss.nodes[i];
+    }
+    for i in n.div_ceil(2)..=n {
+        if 2 * i == n {
+            continue;
+        }
+        let mut lo = if i == 0 { -1. } else { gauss.nodes[i - 1].0 };
+        let mut hi = if i == n { 1. } else { gauss.nodes[i].0 };
+        let sign_lo = stieltjes(D::from(lo)).0 .0 < 0.;
+        for _ in 0..MAX_ITERATIONS {
+            let mid = (lo + hi) / 2.;
+            if mid <= lo || mid >= hi {
+                break;
+            }
+            if (stieltjes(D::from(mid)).0 .0 < 0.) == sign_lo {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let mut x = D::from((lo + hi) / 2.);
+        for _ in 0..3 {
+            let (e, de) = stieltjes(x);
+            x = (x - e / de).normalize();
+        }
+        nodes[2 * i] = x;
+        nodes[2 * (n - i)] = -x;
+    }
+    if n.is_multiple_of(2) {
+        nodes[n] = ZERO;
+    }
+
+    // Σ w_i P_k(x_i) = ∫ P_k = 2 δ_k0 for k in 0..=2n.
+    let len = 2 * n + 1;
+    let mut a = vec![ZERO; len * len];
+    for (col, &x) in nodes.iter().enumerate() {
+        legendre(2 * n, x, |k, pk, _| a[k * len + col] = pk);
+    }
+    let mut weights = vec![ZERO; len];
+    weights[0] = D::from(2.);
+    let mut piv = vec![0; len];
+    linalg::lu_factor(&mut a, len, &mut piv)?;
+    linalg::lu_solve(&a, len, &piv, &mut weights);
+
+    Ok(GaussKronrod {
+        kronrod: Rule { nodes, weights },
+        gauss_weights: gauss.weights,
+    })
+}
@@ -0,0 +1,310 @@
+//! Minimax polynomial approximation by the Remez exchange algorithm.
+//!
+//! The reference systems are solved and the error is evaluated in double-word
+//! arithmetic, so approximations can be levelled well below the precision of
+//! `f64`. Coefficients can be kept in double-word, or constrained to `f64` or
+//! `f32`: those are rounded one at a time, from the constant term up, and the
+//! remaining ones are fitted again around each rounded value.
+
+use crate::f32::round_to_f32;
+use crate::linalg::{self, Error};
+use crate::{Doubled, Normalize};
+
+type D = Doubled<f64>;
+
+const MAX_ITERATIONS: usize = 100;
+
+/// Relative spread of the reference errors at which the exchange stops.
+const TOLERANCE: f64 = 1e-10;
+
+/// Error that is minimized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Weight {
+    /// `|p(x) - f(x)|`.
+    Absolute,
+    /// `|p(x) - f(x)| / |f(x)|`. The target must not vanish on the interval.
+    Relative,
+}
+
+/// Format a coefficient is stored in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    Doubled,
+    F64,
+    F32,
+}
+
+impl Precision {
+    fn round(self, x: D) -> D {
+        match self {
+            Self::Doubled => x,
+            Self::F64 => D::from(x.0 + x.1),
+            Self::F32 => D::from(round_to_f32(x.normalize()) as f64),
+        }
+    }
+}
+
+/// A minimax polynomial.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Approximation {
+    /// Coefficients, constant term first.
+    pub coeffs: Vec<D>,
+    /// Maximum of the weighted error over the interval, as measured on a dense
+    /// grid and at the extrema of the last exchange.
+    pub max_error: f64,
+}
+
+impl Approximation {
+    /// Coefficients rounded to `f64`.
+    pub fn to_f64(&self) -> Vec<f64> {
+        self.coeffs.iter().map(|c| c.0 + c.1).collect()
+    }
+
+    /// Coefficients rounded to `f32`.
+    pub fn to_f32(&self) -> Vec<f32> {
+        self.coeffs
+            .iter()
+            .map(|c| round_to_f32(c.normalize()))
+            .collect()
+    }
+
+    /// Value of the polynomial at `x`.
+    pub fn eval(&self, x: D) -> D {
+        self.coeffs
+            .iter()
+            .rev()
+            .fold(D::from(0.), |p, &c| (p * x + c).normalize())
+    }
+}
+
+/// Minimax polynomial of degree `degree` for `f` on `[a, b]`.
+///
+/// `precision` gives the format of each coefficient, constant term first. It
+/// may be empty, which keeps all of them in double-word, and otherwise has
+/// `degree + 1` entries. An error is returned if a reference system is
+/// singular, which only happens for degenerate targets.
+///
+/// # Panics
+///
+/// Panics if `a >= b` or if `precision` has neither zero nor `degree + 1`
+/// entries.
+pub fn minimax<F: Fn(D) -> D>(
+    f: F,
+    a: f64,
+    b: f64,
+    degree: usize,
+    weight: Weight,
+    precision: &[Precision],
+) -> Result<Approximation, Error> {
+    assert!(a < b, "the interval must not be empty");
+    assert!(precision.is_empty() || precision.len() == degree + 1);
+    let mut remez = Remez {
+        f,
+        a,
+        b,
+        weight,
+        coeffs: vec![D::from(0.); degree + 1],
+        free: (0..=degree).collect(),
+    };
+    let mut max_error = remez.fit()?;
+    for (i, &p) in precision.iter().enumerate() {
+        if p != Precision::Doubled {
+            remez.coeffs[i] = p.round(remez.coeffs[i]);
+            remez.free.retain(|&j| j != i);
+            // Without the lower powers the basis is no longer a Haar system,
+            // and the exchange can end worse than the plain rounding.
+            let rounded = remez.coeffs.clone();
+            let unfitted = remez.max_error(&[]);
+            max_error = remez.fit()?;
+            if max_error > unfitted {
+                remez.coeffs = rounded;
+                max_error = unfitted;
+            }
+        }
+    }
+    Ok(Approximation {
+        coeffs: remez.coeffs,
+        max_error,
+    })
+}
+
+struct Remez<F> {
+    f: F,
+    a: f64,
+    b: f64,
+    weight: Weight,
+    coeffs: Vec<D>,
+    /// Powers whose coefficients are fitted, the others are fixed.
+    free: Vec<usize>,
+}
+
+impl<F: Fn(D) -> D> Remez<F> {
+    /// Weighted error `p(x) - f(x)`.
+    fn error(&self, x: f64) -> f64 {
+        let x = D::from(x);
+        let fx = (self.f)(x);
+        let p = self
+            .coeffs
+            .iter()
+            .rev()
+            .fold(D::from(0.), |p, &c| (p * x + c).normalize());
+        let e = (p - fx).normalize();
+        let scale = match self.weight {
+            Weight::Absolute => D::from(1.),
+            Weight::Relative => fx.abs(),
+        };
+        (e.0 + e.1) / (scale.0 + scale.1)
+    }
+
+    /// Levels the error of the free coefficients and returns the maximum error.
+    fn fit(&mut self) -> Result<f64, Error> {
+        let m = self.free.len();
+        if m == 0 {
+            return Ok(self.max_error(&[]));
+        }
+        let grid = self.grid();
+        let (mid, half) = ((self.a + self.b) / 2., (self.b - self.a) / 2.);
+        let mut reference: Vec<f64> = (0..=m)
+            .map(|j| mid - half * (core::f64::consts::PI * j as f64 / m as f64).cos())
+            .collect();
+        for _ in 0..MAX_ITERATIONS {
+            self.solve(&reference)?;
+            let extrema = self.extrema(&grid);
+            if extrema.len() < m + 1 {
+                // The error does not alternate enough, usually because it is
+                // at the rounding level already.
+                break;
+            }
+            let (mut lo, mut hi) = (0, extrema.len());
+            while hi - lo > m + 1 {
+                if extrema[lo].1.abs() < extrema[hi - 1].1.abs() {
+                    lo += 1;
+                } else {
+                    hi -= 1;
+                }
+            }
+            let extrema = &extrema[lo..hi];
+            let max_error = extrema.iter().map(|e| e.1.abs()).fold(0., f64::max);
+            let min_error = extrema
+                .iter()
+                .map(|e| e.1.abs())
+                .fold(f64::INFINITY, f64::min);
+            reference = extrema.iter().map(|e| e.0).collect();
+            if max_error - min_error <= TOLERANCE * max_error {
+                break;
+            }
+        }
+        self.solve(&reference)?;
+        Ok(self.max_error(&reference))
+    }
+
+    /// Maximum error on the grid and at the points `extra`.
+    fn max_error(&self, extra: &[f64]) -> f64 {
+        self.grid()
+            .iter()
+            .chain(extra)
+            .map(|&x| self.error(x).abs())
+            .fold(0., f64::max)
+    }
+
+    /// Solves `p(x_j) + (-1)^j E / w(x_j) = f(x_j)` for the free coefficients
+    /// and the levelled error `E`.
+    fn solve(&mut self, reference: &[f64]) -> Result<(), Error> {
+        let n = reference.len();
+        let mut a = vec![D::from(0.); n * n];
+        let mut rhs = vec![D::from(0.); n];
+        for (j, &x) in reference.iter().enumerate() {
+            let xd = D::from(x);
+            for (col, &p) in self.free.iter().enumerate() {
+                a[j * n + col] = pow(xd, p);
+            }
+            let scale = match self.weight {
+                Weight::Absolute => D::from(1.),
+                Weight::Relative => (self.f)(xd).abs(),
+            };
+            a[j * n + n - 1] = if j % 2 == 0 { scale } else { -scale };
+            let mut r = (self.f)(xd);
+            for (i, &c) in self.coeffs.iter().enumerate() {
+                if !self.free.contains(&i) {
+                    r = (r - c * pow(xd, i)).normalize();
+                }
+            }
+            rhs[j] = r;
+        }
+        let mut piv = vec![0; n];
+        linalg::lu_factor(&mut a, n, &mut piv)?;
+        linalg::lu_solve(&a, n, &piv, &mut rhs);
+        for (&i, &c) in self.free.iter().zip(&rhs) {
+            self.coeffs[i] = c;
+        }
+        Ok(())
+    }
+
+    /// Chebyshev-spaced points of `[a, b]`, including the ends.
+    fn grid(&self) -> Vec<f64> {
+        let len = 16 * (self.coeffs.len() + 2);
+        let (mid, half) = ((self.a + self.b) / 2., (self.b - self.a) / 2.);
+        (0..len)
+            .map(|i| match i {
+                0 => self.a,
+                _ if i == len - 1 => self.b,
+                _ => mid - half * (core::f64::consts::PI * i as f64 / (len - 1) as f64).cos(),
+            })
+            .collect()
+    }
+
+    /// Extremum of the error in each run of equal sign on the grid, located
+    /// by golden-section search between the neighbouring grid points.
+    fn extrema(&self, grid: &[f64]) -> Vec<(f64, f64)> {
+        let errors: Vec<f64> = grid.iter().map(|&x| self.error(x)).collect();
+        let mut extrema: Vec<(usize, f64)> = Vec::new();
+        for (i, &e) in errors.iter().enumerate() {
+            match extrema.last_mut() {
+                Some(last) if (last.1 < 0.) == (e < 0.) => {
+                    if e.abs() > last.1.abs() {
+                        *last = (i, e);
+                    }
+                }
+                _ => extrema.push((i, e)),
+            }
+        }
+        extrema
+            .into_iter()
+            .map(|(i, e)| {
+                let (mut lo, mut hi) =
+                    (grid[i.saturating_sub(1)], grid[(i + 1).min(grid.len() - 1)]);
+                let s = e.signum();
+                let g = |x: f64| s * self.error(x);
+                let r = (5f64.sqrt() - 1.) / 2.;
+                for _ in 0..40 {
+                    let (x1, x2) = (hi - r * (hi - lo), lo + r * (hi - lo));
+                    if g(x1) < g(x2) {
+                        lo = x1;
+                    } else {
+                        hi = x2;
+                    }
+                }
+                let x = (lo + hi) / 2.;
+                let ex = self.error(x);
+                if ex.abs() > e.abs() {
+                    (x, ex)
+                } else {
+                    (grid[i], e)
+                }
+            })
+            .collect()
+    }
+}
+
+/// `x^p` by repeated squaring.
+fn pow(x: D, mut p: usize) -> D {
+    let (mut r, mut x) = (D::from(1.), x);
+    while p > 0 {
+        if p % 2 == 1 {
+            r = (r * x).normalize();
+        }
+        x = x.square().normalize();
+        p /= 2;
+    }
+    r
+}
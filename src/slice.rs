@@ -0,0 +1,227 @@
+//! Double-word kernels over slices.
+//!
+//! The kernels are vectorized with `core::simd`. On x86 targets that are not
+//! compiled with the `fma` target feature, the presence of FMA and AVX2 is
+//! detected once at runtime and the FMA-based kernels are used when available,
+//! falling back to the Dekker split otherwise.
+
+use crate::Doubled;
+
+/// Calls `kernel::<V.., FMA>(args)`, with `FMA = true` inside a
+/// `#[target_feature(enable = "avx2,fma")]` wrapper when the CPU supports it
+/// and the target was not compiled with FMA already. The arguments are listed
+/// with their concrete types, for the signature of the wrapper.
+macro_rules! dispatch {
+    ($kernel:ident::<$($v:ty),+>($($arg:ident: $ty:ty),*) $(-> $ret:ty)?) => {{
+        #[cfg(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            not(target_feature = "fma")
+        ))]
+        if super::has_fma() {
+            #[target_feature(enable = "avx2,fma")]
+            fn fma($($arg: $ty),*) $(-> $ret)? {
+                $kernel::<$($v,)+ true>($($arg),*)
+            }
+            // SAFETY: the CPU supports `avx2` and `fma`.
+            return unsafe { fma($($arg),*) };
+        }
+        $kernel::<$($v,)+ { cfg!(target_feature = "fma") }>($($arg),*)
+    }};
+}
+
+mod kernels;
+
+/// Floating-point types that have slice kernels.
+pub trait Kernels: Sized {
+    /// Accurate sum of `xs`.
+    fn sum(xs: &[Self]) -> Doubled<Self>;
+    /// Accurate dot product of `xs` and `ys`.
+    fn dot(xs: &[Self], ys: &[Self]) -> Doubled<Self>;
+    /// Evaluates the polynomial with coefficients `coeffs` (constant term first)
+    /// at every point of `xs`.
+    fn polyval(coeffs: &[Self], xs: &[Self], out: &mut [Doubled<Self>]);
+    /// Elementwise product `out[i] = a[i] * b[i]`.
+    fn mul(a: &[Doubled<Self>], b: &[Doubled<Self>], out: &mut [Doubled<Self>]);
+    /// Elementwise quotient `out[i] = a[i] / b[i]`.
+    fn div(a: &[Doubled<Self>], b: &[Doubled<Self>], out: &mut [Doubled<Self>]);
+    /// Matrix product `out = a b` of plain matrices, accumulated in double-word.
+    fn gemm(a: &[Self], b: &[Self], out: &mut [Doubled<Self>], m: usize, k: usize, n: usize);
+    /// Matrix product `out = a b` of double-word matrices.
+    fn gemm_doubled(
+        a: &[Doubled<Self>],
+        b: &[Doubled<Self>],
+        out: &mut [Doubled<Self>],
+        m: usize,
+        k: usize,
+        n: usize,
+    );
+    /// Sparse matrix-vector product `y = A x` of a CSR matrix.
+    fn spmv_csr(
+        row_ptr: &[usize],
+        col_idx: &[usize],
+        values: &[Self],
+        x: &[Self],
+        y: &mut [Doubled<Self>],
+    );
+    /// [`Kernels::spmv_csr`] rounded to the plain type.
+    fn spmv_csr_rounded(
+        row_ptr: &[usize],
+        col_idx: &[usize],
+        values: &[Self],
+        x: &[Self],
+        y: &mut [Self],
+    );
+}
+
+/// Accurate sum of `xs`.
+#[inline]
+pub fn sum<T: Kernels>(xs: &[T]) -> Doubled<T> {
+    T::sum(xs)
+}
+
+/// Accurate dot product of `xs` and `ys`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths.
+#[inline]
+pub fn dot<T: Kernels>(xs: &[T], ys: &[T]) -> Doubled<T> {
+    T::dot(xs, ys)
+}
+
+/// Evaluates the polynomial `coeffs[0] + coeffs[1] * x + ...` with a double-word
+/// Horner scheme at every point of `xs`.
+///
+/// # Panics
+///
+/// Panics if `xs` and `out` have different lengths.
+#[inline]
+pub fn polyval<T: Kernels>(coeffs: &[T], xs: &[T], out: &mut [Doubled<T>]) {
+    T::polyval(coeffs, xs, out)
+}
+
+/// Elementwise product `out[i] = a[i] * b[i]`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths.
+#[inline]
+pub fn mul<T: Kernels>(a: &[Doubled<T>], b: &[Doubled<T>], out: &mut [Doubled<T>]) {
+    T::mul(a, b, out)
+}
+
+/// Elementwise quotient `out[i] = a[i] / b[i]`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths.
+#[inline]
+pub fn div<T: Kernels>(a: &[Doubled<T>], b: &[Doubled<T>], out: &mut [Doubled<T>]) {
+    T::div(a, b, out)
+}
+
+/// Matrix product `out = a b` of the row-major `m × k` matrix `a` and `k × n`
+/// matrix `b`, with every product exact and the sums accumulated in double-word.
+///
+/// The product is computed by register tiles of a few rows of `out` times one
+/// SIMD vector of columns, over blocks of the inner dimension that keep the
+/// rows of `b` in cache. Columns past the last full vector take a scalar path.
+///
+/// # Panics
+///
+/// Panics if the slices do not have `m * k`, `k * n` and `m * n` elements.
+#[inline]
+pub fn gemm<T: Kernels>(a: &[T], b: &[T], out: &mut [Doubled<T>], m: usize, k: usize, n: usize) {
+    T::gemm(a, b, out, m, k, n)
+}
+
+/// [`gemm`] of double-word matrices.
+///
+/// # Panics
+///
+/// Panics if the slices do not have `m * k`, `k * n` and `m * n` elements.
+#[inline]
+pub fn gemm_doubled<T: Kernels>(
+    a: &[Doubled<T>],
+    b: &[Doubled<T>],
+    out: &mut [Doubled<T>],
+    m: usize,
+    k: usize,
+    n: usize,
+) {
+    T::gemm_doubled(a, b, out, m, k, n)
+}
+
+/// Sparse matrix-vector product `y = A x` of the matrix `A` in compressed
+/// sparse row form, with every row accumulated in double-word.
+///
+/// Row `i` has the entries `values[row_ptr[i]..row_ptr[i + 1]]` in the columns
+/// `col_idx[row_ptr[i]..row_ptr[i + 1]]`. Rows are taken a SIMD vector of
+/// entries at a time, with the entries of `x` gathered, and the rest of a
+/// row, or a whole short row, takes a scalar path.
+///
+/// # Panics
+///
+/// Panics if `row_ptr` does not have `y.len() + 1` elements or decreases, if
+/// `col_idx` and `values` do not have `row_ptr[y.len()]` elements, or if a
+/// column index is out of the bounds of `x`.
+#[inline]
+pub fn spmv_csr<T: Kernels>(
+    row_ptr: &[usize],
+    col_idx: &[usize],
+    values: &[T],
+    x: &[T],
+    y: &mut [Doubled<T>],
+) {
+    T::spmv_csr(row_ptr, col_idx, values, x, y)
+}
+
+/// [`spmv_csr`] with every row rounded to the plain type.
+///
+/// # Panics
+///
+/// As [`spmv_csr`].
+#[inline]
+pub fn spmv_csr_rounded<T: Kernels>(
+    row_ptr: &[usize],
+    col_idx: &[usize],
+    values: &[T],
+    x: &[T],
+    y: &mut [T],
+) {
+    T::spmv_csr_rounded(row_ptr, col_idx, values, x, y)
+}
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    not(target_feature = "fma")
+))]
+use detect::has_fma;
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    not(target_feature = "fma")
+))]
+mod detect {
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    const UNKNOWN: u8 = 0;
+    const ABSENT: u8 = 1;
+    const PRESENT: u8 = 2;
+    static FMA: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+    /// Returns `true` if the CPU supports the `avx2` and `fma` features.
+    ///
+    /// The detection runs once; the result is cached.
+    pub(super) fn has_fma() -> bool {
+        match FMA.load(Ordering::Relaxed) {
+            UNKNOWN => {
+                let detected =
+                    std::is_x86_feature_detected!("avx2") && std::is_x86_feature_detected!("fma");
+                FMA.store(if detected { PRESENT } else { ABSENT }, Ordering::Relaxed);
+                detected
+            }
+            state => state == PRESENT,
+        }
+    }
+}
@@ -0,0 +1,514 @@
+//! The slice kernels, written once over [`Vector`], and the [`Kernels`]
+//! implementations that dispatch to them.
+//!
+//! Each kernel takes a `const FMA: bool` parameter that selects the FMA
+//! formulas. The kernels are `#[inline(always)]`, so that the copies
+//! instantiated with `FMA = true` inside `#[target_feature]` functions are
+//! compiled with the feature.
+
+use super::Kernels;
+use crate::base::DoubledBase;
+use crate::*;
+use core::simd::cmp::SimdPartialOrd;
+use core::simd::{Mask, Simd};
+
+/// SIMD vectors the kernels run on, with the loads and stores of their element
+/// type.
+pub(super) trait Vector: DoubledBase {
+    type Scalar: DoubledBase;
+    const LANES: usize;
+    fn from_slice(xs: &[Self::Scalar]) -> Self;
+    /// Loads up to `LANES` values, padding the missing lanes with zeros.
+    fn load_or_default(xs: &[Self::Scalar]) -> Self;
+    fn splat_scalar(x: Self::Scalar) -> Self;
+    /// Loads up to `LANES` double-word values, padding with zeros.
+    fn load(xs: &[Doubled<Self::Scalar>]) -> Doubled<Self>;
+    /// Stores the first `out.len()` lanes of `v`.
+    fn store(v: Doubled<Self>, out: &mut [Doubled<Self::Scalar>]);
+    /// Gathers `xs[idxs[i]]` for the `LANES` indices `idxs`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of the bounds of `xs`.
+    fn gather(xs: &[Self::Scalar], idxs: &[usize]) -> Self;
+    fn reduce_sum(v: Doubled<Self>) -> Doubled<Self::Scalar>;
+}
+
+macro_rules! impl_vector {
+    ($t:ty) => {
+        impl<const N: usize> Vector for Simd<$t, N> {
+            type Scalar = $t;
+            const LANES: usize = N;
+            #[inline(always)]
+            fn from_slice(xs: &[$t]) -> Self {
+                Self::from_slice(xs)
+            }
+            #[inline(always)]
+            fn load_or_default(xs: &[$t]) -> Self {
+                Self::load_or_default(xs)
+            }
+            #[inline(always)]
+            fn splat_scalar(x: $t) -> Self {
+                Self::splat(x)
+            }
+            #[inline(always)]
+            fn load(xs: &[Doubled<$t>]) -> Doubled<Self> {
+                Doubled::<Self>::load_interleaved_or_default(xs)
+            }
+            #[inline(always)]
+            fn store(v: Doubled<Self>, out: &mut [Doubled<$t>]) {
+                v.store_interleaved_select(out, Mask::splat(true));
+            }
+            #[inline(always)]
+            fn gather(xs: &[$t], idxs: &[usize]) -> Self {
+                let idxs = Simd::<usize, N>::from_slice(idxs);
+                assert!(
+                    idxs.simd_lt(Simd::splat(xs.len())).all(),
+                    "column index out of bounds"
+                );
+                Self::gather_or_default(xs, idxs)
+            }
+            #[inline(always)]
+            fn reduce_sum(v: Doubled<Self>) -> Doubled<$t> {
+                v.reduce_sum()
+            }
+        }
+
+        impl<const N: usize> Operand<Simd<$t, N>> for $t {
+            type Vector = Simd<$t, N>;
+            #[inline(always)]
+            fn load(xs: &[Self]) -> Simd<$t, N> {
+                Simd::from_slice(xs)
+            }
+            #[inline(always)]
+            fn mul<const FMA: bool>(x: Self, y: Simd<$t, N>) -> Doubled<Simd<$t, N>> {
+                two_prod::<Simd<$t, N>, FMA>(Simd::splat(x), y)
+            }
+            #[inline(always)]
+            fn mul_scalar<const FMA: bool>(x: Self, y: Self) -> Doubled<$t> {
+                two_prod::<$t, FMA>(x, y)
+            }
+        }
+
+        impl<const N: usize> Operand<Simd<$t, N>> for Doubled<$t> {
+            type Vector = Doubled<Simd<$t, N>>;
+            #[inline(always)]
+            fn load(xs: &[Self]) -> Doubled<Simd<$t, N>> {
+                <Simd<$t, N> as Vector>::load(xs)
+            }
+            #[inline(always)]
+            fn mul<const FMA: bool>(x: Self, y: Doubled<Simd<$t, N>>) -> Doubled<Simd<$t, N>> {
+                let x = Doubled::<Simd<$t, N>>::splat(x);
+                if FMA {
+                    x.mul_fma(y)
+                } else {
+                    x * y
+                }
+            }
+            #[inline(always)]
+            fn mul_scalar<const FMA: bool>(x: Self, y: Self) -> Doubled<$t> {
+                if FMA {
+                    x.mul_fma(y)
+                } else {
+                    x * y
+                }
+            }
+        }
+    };
+}
+
+impl_vector!(f32);
+impl_vector!(f64);
+
+#[inline(always)]
+pub(super) fn sum<V: Vector, const FMA: bool>(xs: &[V::Scalar]) -> Doubled<V::Scalar> {
+    let mut acc = Doubled::new(V::splat(0.), V::splat(0.));
+    let chunks = xs.chunks_exact(V::LANES);
+    let tail = V::load_or_default(chunks.remainder());
+    for x in chunks {
+        acc += V::from_slice(x);
+    }
+    acc += tail;
+    V::reduce_sum(acc)
+}
+
+#[inline(always)]
+pub(super) fn dot<V: Vector, const FMA: bool>(
+    xs: &[V::Scalar],
+    ys: &[V::Scalar],
+) -> Doubled<V::Scalar> {
+    let mut acc = Doubled::new(V::splat(0.), V::splat(0.));
+    let xc = xs.chunks_exact(V::LANES);
+    let yc = ys.chunks_exact(V::LANES);
+    let xt = V::load_or_default(xc.remainder());
+    let yt = V::load_or_default(yc.remainder());
+    for (x, y) in xc.zip(yc) {
+        acc += two_prod::<V, FMA>(V::from_slice(x), V::from_slice(y));
+    }
+    acc += two_prod::<V, FMA>(xt, yt);
+    V::reduce_sum(acc)
+}
+
+#[inline(always)]
+pub(super) fn polyval<V: Vector, const FMA: bool>(
+    coeffs: &[V::Scalar],
+    xs: &[V::Scalar],
+    out: &mut [Doubled<V::Scalar>],
+) {
+    let mut xc = xs.chunks_exact(V::LANES);
+    let mut oc = out.chunks_exact_mut(V::LANES);
+    for (x, o) in xc.by_ref().zip(oc.by_ref()) {
+        V::store(horner::<V, FMA>(coeffs, V::from_slice(x)), o);
+    }
+    let xt = V::load_or_default(xc.remainder());
+    V::store(horner::<V, FMA>(coeffs, xt), oc.into_remainder());
+}
+
+#[inline(always)]
+pub(super) fn mul<V: Vector, const FMA: bool>(
+    a: &[Doubled<V::Scalar>],
+    b: &[Doubled<V::Scalar>],
+    out: &mut [Doubled<V::Scalar>],
+) {
+    for ((a, b), o) in a
+        .chunks(V::LANES)
+        .zip(b.chunks(V::LANES))
+        .zip(out.chunks_mut(V::LANES))
+    {
+        let (a, b) = (V::load(a), V::load(b));
+        V::store(if FMA { a.mul_fma(b) } else { a * b }, o);
+    }
+}
+
+#[inline(always)]
+pub(super) fn div<V: Vector, const FMA: bool>(
+    a: &[Doubled<V::Scalar>],
+    b: &[Doubled<V::Scalar>],
+    out: &mut [Doubled<V::Scalar>],
+) {
+    for ((a, b), o) in a
+        .chunks(V::LANES)
+        .zip(b.chunks(V::LANES))
+        .zip(out.chunks_mut(V::LANES))
+    {
+        let (a, b) = (V::load(a), V::load(b));
+        V::store(if FMA { a.div_fma(b) } else { a / b }, o);
+    }
+}
+
+/// Rows of a register tile of [`gemm`].
+const MR: usize = 4;
+/// Length of the blocks of the inner dimension of [`gemm`].
+const KC: usize = 256;
+
+/// Element types of the [`gemm`] operands on `V`: its scalar or a double-word
+/// of it.
+pub(super) trait Operand<V: Vector>: Copy {
+    type Vector: Copy;
+    fn load(xs: &[Self]) -> Self::Vector;
+    /// Product of `x` broadcast to every lane with `y`.
+    fn mul<const FMA: bool>(x: Self, y: Self::Vector) -> Doubled<V>;
+    fn mul_scalar<const FMA: bool>(x: Self, y: Self) -> Doubled<V::Scalar>;
+}
+
+#[inline(always)]
+pub(super) fn gemm<V: Vector, T: Operand<V>, const FMA: bool>(
+    a: &[T],
+    b: &[T],
+    out: &mut [Doubled<V::Scalar>],
+    m: usize,
+    k: usize,
+    n: usize,
+) {
+    let zero = V::Scalar::splat(0.);
+    out.fill(Doubled::new(zero, zero));
+    let nv = n - n % V::LANES;
+    for p0 in (0..k).step_by(KC) {
+        let p1 = (p0 + KC).min(k);
+        let mut i = 0;
+        while i + MR <= m {
+            for j in (0..nv).step_by(V::LANES) {
+                gemm_tile::<V, T, FMA, MR>(a, b, out, k, n, i, j, p0..p1);
+            }
+            i += MR;
+        }
+        for i in i..m {
+            for j in (0..nv).step_by(V::LANES) {
+                gemm_tile::<V, T, FMA, 1>(a, b, out, k, n, i, j, p0..p1);
+            }
+        }
+    }
+    for i in 0..m {
+        for j in nv..n {
+            let mut acc = Doubled::new(zero, zero);
+            for p in 0..k {
+                acc += T::mul_scalar::<FMA>(a[i * k + p], b[p * n + j]);
+            }
+            out[i * n + j] = acc.normalize();
+        }
+    }
+}
+
+/// Adds to the `R × LANES` tile of `out` at row `i` and column `j` the
+/// products over the inner indices `ps`.
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn gemm_tile<V: Vector, T: Operand<V>, const FMA: bool, const R: usize>(
+    a: &[T],
+    b: &[T],
+    out: &mut [Doubled<V::Scalar>],
+    k: usize,
+    n: usize,
+    i: usize,
+    j: usize,
+    ps: core::ops::Range<usize>,
+) {
+    let mut acc: [Doubled<V>; R] =
+        core::array::from_fn(|r| V::load(&out[(i + r) * n + j..][..V::LANES]));
+    for p in ps {
+        let y = T::load(&b[p * n + j..][..V::LANES]);
+        for (r, acc) in acc.iter_mut().enumerate() {
+            *acc += T::mul::<FMA>(a[(i + r) * k + p], y);
+        }
+    }
+    for (r, acc) in acc.into_iter().enumerate() {
+        V::store(acc.normalize(), &mut out[(i + r) * n + j..][..V::LANES]);
+    }
+}
+
+#[inline(always)]
+pub(super) fn spmv_csr<V: Vector, Y: From<Doubled<V::Scalar>>, const FMA: bool>(
+    row_ptr: &[usize],
+    col_idx: &[usize],
+    values: &[V::Scalar],
+    x: &[V::Scalar],
+    y: &mut [Y],
+) {
+    assert_eq!(row_ptr.len(), y.len() + 1);
+    assert!(
+        row_ptr.windows(2).all(|r| r[0] <= r[1]),
+        "row_ptr must be non-decreasing"
+    );
+    assert_eq!(col_idx.len(), row_ptr[y.len()]);
+    assert_eq!(values.len(), col_idx.len());
+    for (y, r) in y.iter_mut().zip(row_ptr.windows(2)) {
+        let vc = values[r[0]..r[1]].chunks_exact(V::LANES);
+        let ic = col_idx[r[0]..r[1]].chunks_exact(V::LANES);
+        let (vt, it) = (vc.remainder(), ic.remainder());
+        let mut acc = Doubled::new(V::splat(0.), V::splat(0.));
+        for (v, i) in vc.zip(ic) {
+            acc += two_prod::<V, FMA>(V::from_slice(v), V::gather(x, i));
+        }
+        let mut acc = V::reduce_sum(acc);
+        for (&v, &i) in vt.iter().zip(it) {
+            assert!(i < x.len(), "column index out of bounds");
+            acc += two_prod::<V::Scalar, FMA>(v, x[i]);
+        }
+        *y = Y::from(acc.normalize());
+    }
+}
+
+#[inline(always)]
+pub(super) fn two_prod<V: DoubledBase, const FMA: bool>(x: V, y: V) -> Doubled<V> {
+    if FMA {
+        eft::two_prod_fma(x, y)
+    } else {
+        x.mul_as_doubled(y)
+    }
+}
+
+#[inline(always)]
+fn horner<V: Vector, const FMA: bool>(coeffs: &[V::Scalar], x: V) -> Doubled<V> {
+    let mut r = Doubled::new(V::splat(0.), V::splat(0.));
+    for &c in coeffs.iter().rev() {
+        r = if FMA { r.mul_single_fma(x) } else { r * x } + V::splat_scalar(c);
+    }
+    r
+}
+
+/// Implements [`Kernels`] for `$t` with the kernels on the vector `$v`.
+macro_rules! impl_kernels {
+    ($t:ident, $v:ty) => {
+        impl Kernels for $t {
+            fn sum(xs: &[Self]) -> Doubled<Self> {
+                dispatch!(sum::<$v>(xs: &[$t]) -> Doubled<$t>)
+            }
+
+            fn dot(xs: &[Self], ys: &[Self]) -> Doubled<Self> {
+                assert_eq!(xs.len(), ys.len());
+                dispatch!(dot::<$v>(xs: &[$t], ys: &[$t]) -> Doubled<$t>)
+            }
+
+            fn polyval(coeffs: &[Self], xs: &[Self], out: &mut [Doubled<Self>]) {
+                assert_eq!(xs.len(), out.len());
+                dispatch!(polyval::<$v>(coeffs: &[$t], xs: &[$t], out: &mut [Doubled<$t>]))
+            }
+
+            fn mul(a: &[Doubled<Self>], b: &[Doubled<Self>], out: &mut [Doubled<Self>]) {
+                assert_eq!(a.len(), b.len());
+                assert_eq!(a.len(), out.len());
+                dispatch!(mul::<$v>(a: &[Doubled<$t>], b: &[Doubled<$t>], out: &mut [Doubled<$t>]))
+            }
+
+            fn div(a: &[Doubled<Self>], b: &[Doubled<Self>], out: &mut [Doubled<Self>]) {
+                assert_eq!(a.len(), b.len());
+                assert_eq!(a.len(), out.len());
+                dispatch!(div::<$v>(a: &[Doubled<$t>], b: &[Doubled<$t>], out: &mut [Doubled<$t>]))
+            }
+
+            fn gemm(
+                a: &[Self],
+                b: &[Self],
+                out: &mut [Doubled<Self>],
+                m: usize,
+                k: usize,
+                n: usize,
+            ) {
+                assert_eq!(a.len(), m * k);
+                assert_eq!(b.len(), k * n);
+                assert_eq!(out.len(), m * n);
+                dispatch!(gemm::<$v, $t>(
+                    a: &[$t],
+                    b: &[$t],
+                    out: &mut [Doubled<$t>],
+                    m: usize,
+                    k: usize,
+                    n: usize
+                ))
+            }
+
+            fn gemm_doubled(
+                a: &[Doubled<Self>],
+                b: &[Doubled<Self>],
+                out: &mut [Doubled<Self>],
+                m: usize,
+                k: usize,
+                n: usize,
+            ) {
+                assert_eq!(a.len(), m * k);
+                assert_eq!(b.len(), k * n);
+                assert_eq!(out.len(), m * n);
+                dispatch!(gemm::<$v, Doubled<$t>>(
+                    a: &[Doubled<$t>],
+                    b: &[Doubled<$t>],
+                    out: &mut [Doubled<$t>],
+                    m: usize,
+                    k: usize,
+                    n: usize
+                ))
+            }
+
+            fn spmv_csr(
+                row_ptr: &[usize],
+                col_idx: &[usize],
+                values: &[Self],
+                x: &[Self],
+                y: &mut [Doubled<Self>],
+            ) {
+                dispatch!(spmv_csr::<$v, Doubled<$t>>(
+                    row_ptr: &[usize],
+                    col_idx: &[usize],
+                    values: &[$t],
+                    x: &[$t],
+                    y: &mut [Doubled<$t>]
+                ))
+            }
+
+            fn spmv_csr_rounded(
+                row_ptr: &[usize],
+                col_idx: &[usize],
+                values: &[Self],
+                x: &[Self],
+                y: &mut [Self],
+            ) {
+                dispatch!(spmv_csr::<$v, $t>(
+                    row_ptr: &[usize],
+                    col_idx: &[usize],
+                    values: &[$t],
+                    x: &[$t],
+                    y: &mut [$t]
+                ))
+            }
+        }
+    };
+}
+
+impl_kernels!(f32, F32x);
+impl_kernels!(f64, F64x);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic values of mixed signs and magnitudes.
+    fn values(len: usize, seed: u64) -> Vec<f64> {
+        let mut s = seed;
+        (0..len)
+            .map(|_| {
+                s = s
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let x = (s >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+                x * (1 << (s % 16)) as f64
+            })
+            .collect()
+    }
+
+    fn doubled(len: usize, seed: u64) -> Vec<Doubled<f64>> {
+        let lo = values(len, seed + 1);
+        values(len, seed)
+            .iter()
+            .zip(lo)
+            .map(|(&hi, lo)| Doubled::new(hi, lo * 1e-17 * hi.abs()).normalize())
+            .collect()
+    }
+
+    type Results = (
+        Doubled<f64>,
+        Doubled<f64>,
+        Vec<Doubled<f64>>,
+        Vec<Doubled<f64>>,
+        Vec<Doubled<f64>>,
+        Vec<Doubled<f64>>,
+    );
+
+    /// The kernels with the given `FMA`, called directly rather than through
+    /// the runtime dispatch.
+    fn run<const FMA: bool>(
+        xs: &[f64],
+        ys: &[f64],
+        a: &[Doubled<f64>],
+        b: &[Doubled<f64>],
+    ) -> Results {
+        let mut p = vec![Doubled::new(0., 0.); xs.len()];
+        let mut m = p.clone();
+        let mut d = p.clone();
+        polyval::<F64x, FMA>(&[0.5, -3., 1e-3, 7.], xs, &mut p);
+        mul::<F64x, FMA>(a, b, &mut m);
+        div::<F64x, FMA>(a, b, &mut d);
+        let mut g = vec![Doubled::new(0., 0.); 9];
+        gemm::<F64x, f64, FMA>(&xs[..15], &ys[..15], &mut g, 3, 5, 3);
+        (sum::<F64x, FMA>(xs), dot::<F64x, FMA>(xs, ys), p, m, d, g)
+    }
+
+    #[test]
+    fn fma_and_split_agree() {
+        let (xs, ys) = (values(103, 11), values(103, 12));
+        let (a, b) = (doubled(103, 13), doubled(103, 15));
+        let split = run::<false>(&xs, &ys, &a, &b);
+        let fma = run::<true>(&xs, &ys, &a, &b);
+        // The sums of exact products do not depend on how the products are formed.
+        assert_eq!(split.0, fma.0);
+        assert_eq!(split.1, fma.1);
+        assert_eq!(split.5, fma.5);
+        for (x, y) in [(&split.2, &fma.2), (&split.3, &fma.3), (&split.4, &fma.4)] {
+            for (&x, &y) in x.iter().zip(y) {
+                let e = x - y;
+                assert!(
+                    (e.0 + e.1).abs() <= 1e-28 * y.0.abs().max(1.),
+                    "{x:?} {y:?}"
+                );
+            }
+        }
+    }
+}
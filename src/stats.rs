@@ -0,0 +1,287 @@
+//! Streaming mean, variance and skewness in double-word arithmetic.
+//!
+//! [`RunningStats`] keeps the mean and the central moment sums of Welford's
+//! algorithm as [`Doubled<f64>`]. The update subtracts the running mean from
+//! each value in double-word, so data with a large offset, such as timestamps
+//! or readings around `1e9`, keeps its variance where the textbook `f64`
+//! formulas cancel to noise. Partial results of chunks can be combined with
+//! [`RunningStats::merge`], which makes the type usable as a parallel
+//! reduction, and `LaneStats` runs the update on every lane of a vector.
+
+use crate::{Doubled, Normalize};
+#[cfg(feature = "simd")]
+use crate::{F64x, LANES};
+#[cfg(feature = "simd")]
+use core::simd::Simd;
+
+type D = Doubled<f64>;
+
+/// Count, mean, variance, skewness, minimum and maximum of a stream of `f64`.
+///
+/// NaN values propagate to the moments but are ignored by the minimum and the
+/// maximum, as with [`f64::min`] and [`f64::max`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RunningStats {
+    count: u64,
+    mean: D,
+    /// `Σ (x - mean)²`.
+    m2: D,
+    /// `Σ (x - mean)³`.
+    m3: D,
+    min: f64,
+    max: f64,
+}
+
+impl Default for RunningStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunningStats {
+    /// Statistics of the empty stream.
+    pub const fn new() -> Self {
+        Self {
+            count: 0,
+            mean: Doubled::new(0., 0.),
+            m2: Doubled::new(0., 0.),
+            m3: Doubled::new(0., 0.),
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Adds `x` to the stream.
+    pub fn push(&mut self, x: f64) {
+        let n1 = self.count as f64;
+        self.count += 1;
+        let n = self.count as f64;
+        let delta = (D::from(x) - self.mean).normalize();
+        let delta_n = (delta / D::from(n)).normalize();
+        let term = ((delta * delta_n).normalize() * n1).normalize();
+        self.mean = (self.mean + delta_n).normalize();
+        let m3 = ((term * delta_n).normalize() * (n - 2.)).normalize();
+        let m3 = (m3 - (delta_n * self.m2).normalize() * 3.).normalize();
+        self.m3 = (self.m3 + m3).normalize();
+        self.m2 = (self.m2 + term).normalize();
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+    }
+
+    /// Adds every value of `xs` to the stream.
+    ///
+    /// The values are spread over the lanes of a [`LaneStats`], and the lanes
+    /// are merged at the end.
+    #[cfg(feature = "simd")]
+    pub fn push_slice(&mut self, xs: &[f64]) {
+        let chunks = xs.chunks_exact(LANES);
+        let rest = chunks.remainder();
+        let mut lanes = LaneStats::<LANES>::new();
+        for chunk in chunks {
+            lanes.push(F64x::from_slice(chunk));
+        }
+        self.merge(&lanes.reduce());
+        for &x in rest {
+            self.push(x);
+        }
+    }
+
+    /// Combines the statistics of `other` into `self`, as if its values had
+    /// been pushed after those of `self`.
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let (na, nb) = (self.count as f64, other.count as f64);
+        self.count += other.count;
+        let n = D::from(self.count as f64);
+        // Fractions of the combined count, so that `na nb` is never rounded.
+        let (fa, fb) = ((D::from(na) / n).normalize(), (D::from(nb) / n).normalize());
+        let delta = (other.mean - self.mean).normalize();
+        let delta_b = (delta * fb).normalize();
+        let delta2 = (delta * delta_b).normalize();
+
+        // m3 += δ³ na nb (na - nb) / n² + 3 δ (na m2b - nb m2a) / n
+        let cross = ((fa * other.m2).normalize() - (fb * self.m2).normalize()).normalize();
+        let m3 = ((delta2 * delta).normalize() * (fa * (na - nb)).normalize()).normalize();
+        let m3 = (m3 + (delta * cross).normalize() * 3.).normalize();
+        self.m3 = ((self.m3 + other.m3).normalize() + m3).normalize();
+        // m2 += δ² na nb / n
+        self.m2 = ((self.m2 + other.m2).normalize() + (delta2 * na).normalize()).normalize();
+        self.mean = (self.mean + delta_b).normalize();
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Number of values pushed.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Mean, or NaN for the empty stream.
+    pub fn mean(&self) -> D {
+        if self.count == 0 {
+            D::from(f64::NAN)
+        } else {
+            self.mean
+        }
+    }
+
+    /// Sample variance, `Σ (x - mean)² / (count - 1)`, or NaN with fewer than
+    /// two values.
+    pub fn variance(&self) -> D {
+        if self.count < 2 {
+            D::from(f64::NAN)
+        } else {
+            (self.m2 / D::from((self.count - 1) as f64)).normalize()
+        }
+    }
+
+    /// Population variance, `Σ (x - mean)² / count`, or NaN for the empty
+    /// stream.
+    pub fn population_variance(&self) -> D {
+        if self.count == 0 {
+            D::from(f64::NAN)
+        } else {
+            (self.m2 / D::from(self.count as f64)).normalize()
+        }
+    }
+
+    /// Population skewness, `√count Σ (x - mean)³ / (Σ (x - mean)²)^(3/2)`.
+    /// It is NaN for the empty stream and for constant data.
+    #[cfg(feature = "std")]
+    pub fn skewness(&self) -> D {
+        if self.count == 0 || self.m2.0 == 0. {
+            return D::from(f64::NAN);
+        }
+        let n = D::from(self.count as f64);
+        let m2 = (self.m2 / n).normalize();
+        let s3 = (m2 * m2.sqrt().normalize()).normalize();
+        ((self.m3 / n).normalize() / s3).normalize()
+    }
+
+    /// Smallest value, or NaN for the empty stream.
+    pub fn min(&self) -> f64 {
+        if self.count == 0 {
+            f64::NAN
+        } else {
+            self.min
+        }
+    }
+
+    /// Largest value, or NaN for the empty stream.
+    pub fn max(&self) -> f64 {
+        if self.count == 0 {
+            f64::NAN
+        } else {
+            self.max
+        }
+    }
+}
+
+impl Extend<f64> for RunningStats {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, iter: I) {
+        for x in iter {
+            self.push(x);
+        }
+    }
+}
+
+impl FromIterator<f64> for RunningStats {
+    fn from_iter<I: IntoIterator<Item = f64>>(iter: I) -> Self {
+        let mut stats = Self::new();
+        stats.extend(iter);
+        stats
+    }
+}
+
+/// [`RunningStats`] of `N` interleaved streams of the same length, updated
+/// one vector at a time.
+///
+/// Each lane runs the Welford update of [`RunningStats::push`] on its own
+/// values; [`LaneStats::reduce`] merges the lanes into one [`RunningStats`].
+#[cfg(feature = "simd")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LaneStats<const N: usize> {
+    count: u64,
+    mean: Doubled<Simd<f64, N>>,
+    m2: Doubled<Simd<f64, N>>,
+    m3: Doubled<Simd<f64, N>>,
+    min: Simd<f64, N>,
+    max: Simd<f64, N>,
+}
+
+#[cfg(feature = "simd")]
+impl<const N: usize> Default for LaneStats<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<const N: usize> LaneStats<N> {
+    /// Statistics of `N` empty streams.
+    pub const fn new() -> Self {
+        let zero = Doubled::<Simd<f64, N>>::splat(Doubled::new(0., 0.));
+        Self {
+            count: 0,
+            mean: zero,
+            m2: zero,
+            m3: zero,
+            min: Simd::splat(f64::INFINITY),
+            max: Simd::splat(f64::NEG_INFINITY),
+        }
+    }
+
+    /// Adds lane `i` of `x` to stream `i`.
+    pub fn push(&mut self, x: Simd<f64, N>) {
+        use core::simd::num::SimdFloat;
+
+        let splat = Simd::<f64, N>::splat;
+        let n1 = self.count as f64;
+        self.count += 1;
+        let n = self.count as f64;
+        let delta = (Doubled::from(x) - self.mean).normalize();
+        let delta_n = (delta / Doubled::from(splat(n))).normalize();
+        let term = ((delta * delta_n).normalize() * splat(n1)).normalize();
+        self.mean = (self.mean + delta_n).normalize();
+        let m3 = ((term * delta_n).normalize() * splat(n - 2.)).normalize();
+        let m3 = (m3 - (delta_n * self.m2).normalize() * splat(3.)).normalize();
+        self.m3 = (self.m3 + m3).normalize();
+        self.m2 = (self.m2 + term).normalize();
+        self.min = self.min.simd_min(x);
+        self.max = self.max.simd_max(x);
+    }
+
+    /// Number of vectors pushed, which is the count of every lane.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Statistics of each lane.
+    pub fn lanes(&self) -> [RunningStats; N] {
+        let (mean, m2, m3) = (self.mean.to_array(), self.m2.to_array(), self.m3.to_array());
+        let (min, max) = (self.min.to_array(), self.max.to_array());
+        core::array::from_fn(|i| RunningStats {
+            count: self.count,
+            mean: mean[i],
+            m2: m2[i],
+            m3: m3[i],
+            min: min[i],
+            max: max[i],
+        })
+    }
+
+    /// Statistics of the values of all lanes.
+    pub fn reduce(&self) -> RunningStats {
+        let mut stats = RunningStats::new();
+        for lane in self.lanes() {
+            stats.merge(&lane);
+        }
+        stats
+    }
+}
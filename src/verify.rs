@@ -0,0 +1,581 @@
+//! Exact reference arithmetic for measuring the accuracy of [`Doubled`] results.
+//!
+//! [`BigFloat`] is an arbitrary-precision binary floating-point number. Sums,
+//! differences and products of `BigFloat`s are exact; quotients and square roots
+//! are computed to a caller-chosen number of bits.
+
+use crate::Doubled;
+use core::cmp::Ordering;
+use core::ops::{Add, Mul, Neg, Sub};
+
+/// Arbitrary-precision natural number, little-endian 32-bit limbs without
+/// leading zero limbs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Nat(Vec<u32>);
+
+impl Nat {
+    fn zero() -> Self {
+        Self(Vec::new())
+    }
+
+    fn from_u64(x: u64) -> Self {
+        let mut n = Self(vec![x as u32, (x >> 32) as u32]);
+        n.trim();
+        n
+    }
+
+    fn trim(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn bits(&self) -> i64 {
+        match self.0.last() {
+            None => 0,
+            Some(&top) => (self.0.len() as i64 - 1) * 32 + (32 - top.leading_zeros() as i64),
+        }
+    }
+
+    fn trailing_zeros(&self) -> i64 {
+        let mut n = 0;
+        for &limb in &self.0 {
+            if limb != 0 {
+                return n + limb.trailing_zeros() as i64;
+            }
+            n += 32;
+        }
+        n
+    }
+
+    fn bit(&self, i: i64) -> bool {
+        let limb = (i / 32) as usize;
+        limb < self.0.len() && (self.0[limb] >> (i % 32)) & 1 == 1
+    }
+
+    fn to_u64(&self) -> u64 {
+        debug_assert!(self.bits() <= 64);
+        self.0
+            .iter()
+            .rev()
+            .fold(0, |acc, &limb| (acc << 32) | limb as u64)
+    }
+
+    fn shl(&self, n: i64) -> Self {
+        if self.is_zero() {
+            return Self::zero();
+        }
+        let (limbs, bits) = ((n / 32) as usize, (n % 32) as u32);
+        let mut r = vec![0; limbs];
+        let mut carry = 0;
+        for &limb in &self.0 {
+            let wide = ((limb as u64) << bits) | carry;
+            r.push(wide as u32);
+            carry = wide >> 32;
+        }
+        r.push(carry as u32);
+        let mut r = Self(r);
+        r.trim();
+        r
+    }
+
+    fn shr(&self, n: i64) -> Self {
+        let (limbs, bits) = ((n / 32) as usize, (n % 32) as u32);
+        if limbs >= self.0.len() {
+            return Self::zero();
+        }
+        let src = &self.0[limbs..];
+        let mut r: Vec<u32> = (0..src.len())
+            .map(|i| {
+                let hi = src.get(i + 1).copied().unwrap_or(0) as u64;
+                ((((hi << 32) | src[i] as u64) >> bits) & 0xffff_ffff) as u32
+            })
+            .collect();
+        while r.last() == Some(&0) {
+            r.pop();
+        }
+        Self(r)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let (long, short) = if self.0.len() >= other.0.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut r = Vec::with_capacity(long.0.len() + 1);
+        let mut carry = 0;
+        for (i, &limb) in long.0.iter().enumerate() {
+            let wide = limb as u64 + short.0.get(i).copied().unwrap_or(0) as u64 + carry;
+            r.push(wide as u32);
+            carry = wide >> 32;
+        }
+        r.push(carry as u32);
+        let mut r = Self(r);
+        r.trim();
+        r
+    }
+
+    /// `self - other`, which must not be negative.
+    fn sub(&self, other: &Self) -> Self {
+        debug_assert!(self.cmp(other) != Ordering::Less);
+        let mut r = Vec::with_capacity(self.0.len());
+        let mut borrow = 0;
+        for (i, &limb) in self.0.iter().enumerate() {
+            let wide = limb as i64 - other.0.get(i).copied().unwrap_or(0) as i64 - borrow;
+            r.push(wide.rem_euclid(1 << 32) as u32);
+            borrow = (wide < 0) as i64;
+        }
+        let mut r = Self(r);
+        r.trim();
+        r
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        if self.is_zero() || other.is_zero() {
+            return Self::zero();
+        }
+        let mut r = vec![0u32; self.0.len() + other.0.len()];
+        for (i, &a) in self.0.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.0.iter().enumerate() {
+                let wide = a as u64 * b as u64 + r[i + j] as u64 + carry;
+                r[i + j] = wide as u32;
+                carry = wide >> 32;
+            }
+            r[i + other.0.len()] = carry as u32;
+        }
+        let mut r = Self(r);
+        r.trim();
+        r
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .len()
+            .cmp(&other.0.len())
+            .then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+
+    /// Truncating division by a non-zero `other`.
+    fn div(&self, other: &Self) -> Self {
+        debug_assert!(!other.is_zero());
+        let mut q = vec![0u32; self.0.len()];
+        // The running remainder stays below `2 * other`, one limb longer at most.
+        let mut r = vec![0u32; other.0.len() + 1];
+        for i in (0..self.bits()).rev() {
+            let mut carry = self.bit(i) as u32;
+            for limb in r.iter_mut() {
+                let next = *limb >> 31;
+                *limb = (*limb << 1) | carry;
+                carry = next;
+            }
+            if Self::ge_limbs(&r, &other.0) {
+                let mut borrow = 0;
+                for (j, limb) in r.iter_mut().enumerate() {
+                    let wide = *limb as i64 - other.0.get(j).copied().unwrap_or(0) as i64 - borrow;
+                    *limb = wide.rem_euclid(1 << 32) as u32;
+                    borrow = (wide < 0) as i64;
+                }
+                q[(i / 32) as usize] |= 1 << (i % 32);
+            }
+        }
+        let mut q = Self(q);
+        q.trim();
+        q
+    }
+
+    /// `a >= b` for limb slices where `a` may have leading zero limbs.
+    fn ge_limbs(a: &[u32], b: &[u32]) -> bool {
+        let len = a.len().max(b.len());
+        for j in (0..len).rev() {
+            let (x, y) = (
+                a.get(j).copied().unwrap_or(0),
+                b.get(j).copied().unwrap_or(0),
+            );
+            if x != y {
+                return x > y;
+            }
+        }
+        true
+    }
+
+    /// Integer square root, rounded down.
+    fn isqrt(&self) -> Self {
+        if self.is_zero() {
+            return Self::zero();
+        }
+        // Start from an overestimate built from the 62 or 63 leading bits.
+        let shift = (self.bits() - 63).max(0) & !1;
+        let top = self.shr(shift).to_u64() as f64 + 1.;
+        let mut x = Self::from_u64(top.sqrt() as u64 + 2).shl(shift / 2);
+        loop {
+            let y = x.add(&self.div(&x)).shr(1);
+            if y.cmp(&x) != Ordering::Less {
+                return x;
+            }
+            x = y;
+        }
+    }
+}
+
+/// Error of converting an infinity or a NaN to [`BigFloat`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NonFiniteError;
+
+impl core::fmt::Display for NonFiniteError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("value is not finite")
+    }
+}
+
+impl std::error::Error for NonFiniteError {}
+
+/// Arbitrary-precision binary floating-point number `±mantissa * 2^exponent`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BigFloat {
+    negative: bool,
+    // Odd, or zero.
+    mantissa: Nat,
+    exponent: i64,
+}
+
+impl BigFloat {
+    fn from_parts(negative: bool, mantissa: Nat, exponent: i64) -> Self {
+        if mantissa.is_zero() {
+            return Self::zero();
+        }
+        let tz = mantissa.trailing_zeros();
+        Self {
+            negative,
+            mantissa: mantissa.shr(tz),
+            exponent: exponent + tz,
+        }
+    }
+
+    #[inline]
+    pub fn zero() -> Self {
+        Self {
+            negative: false,
+            mantissa: Nat::zero(),
+            exponent: 0,
+        }
+    }
+
+    #[inline]
+    pub fn is_zero(&self) -> bool {
+        self.mantissa.is_zero()
+    }
+
+    #[inline]
+    pub fn is_sign_negative(&self) -> bool {
+        self.negative
+    }
+
+    #[inline]
+    pub fn abs(&self) -> Self {
+        Self {
+            negative: false,
+            ..self.clone()
+        }
+    }
+
+    /// Exponent of the leading bit, `floor(log2(|self|))`, or `None` for zero.
+    #[inline]
+    pub fn ilogb(&self) -> Option<i64> {
+        (!self.is_zero()).then(|| self.exponent + self.mantissa.bits() - 1)
+    }
+
+    /// Exact `self * 2^e`.
+    #[inline]
+    pub fn ldexp(&self, e: i64) -> Self {
+        if self.is_zero() {
+            return Self::zero();
+        }
+        Self {
+            exponent: self.exponent + e,
+            ..self.clone()
+        }
+    }
+
+    /// Quotient truncated toward zero to at least `bits` significant bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is zero.
+    pub fn div(&self, other: &Self, bits: u32) -> Self {
+        assert!(!other.is_zero(), "division by zero");
+        if self.is_zero() {
+            return Self::zero();
+        }
+        let shift = (bits as i64 + other.mantissa.bits() - self.mantissa.bits()).max(0);
+        Self::from_parts(
+            self.negative != other.negative,
+            self.mantissa.shl(shift).div(&other.mantissa),
+            self.exponent - other.exponent - shift,
+        )
+    }
+
+    /// Square root truncated toward zero to at least `bits` significant bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is negative.
+    pub fn sqrt(&self, bits: u32) -> Self {
+        assert!(!self.negative, "square root of a negative number");
+        if self.is_zero() {
+            return Self::zero();
+        }
+        let (mut m, mut e) = (self.mantissa.clone(), self.exponent);
+        if e % 2 != 0 {
+            m = m.shl(1);
+            e -= 1;
+        }
+        let shift = (bits as i64 - m.bits() / 2 + 1).max(0);
+        Self::from_parts(false, m.shl(2 * shift).isqrt(), e / 2 - shift)
+    }
+
+    /// Rounds to the nearest multiple of `2^lsb`, ties to even.
+    fn round_at(&self, lsb: i64) -> Self {
+        if self.is_zero() || self.exponent >= lsb {
+            return self.clone();
+        }
+        let shift = lsb - self.exponent;
+        let mut q = self.mantissa.shr(shift);
+        // The mantissa is odd, so the discarded bits below the half bit are
+        // non-zero exactly when there are any.
+        let half = self.mantissa.bit(shift - 1);
+        if half && (shift > 1 || q.bit(0)) {
+            q = q.add(&Nat::from_u64(1));
+        }
+        Self::from_parts(self.negative, q, lsb)
+    }
+
+    /// Rounds to `digits` significant bits with the exponent range of a binary
+    /// format whose smallest subnormal is `2^min_lsb` and largest exponent is
+    /// `max_exp`, and converts to `f64`.
+    fn to_binary(&self, digits: i64, min_lsb: i64, max_exp: i64) -> f64 {
+        let sign = if self.negative { -1. } else { 1. };
+        let Some(e) = self.ilogb() else {
+            return 0.;
+        };
+        let r = self.round_at((e - digits + 1).max(min_lsb));
+        match r.ilogb() {
+            None => sign * 0.,
+            Some(e) if e > max_exp => sign * f64::INFINITY,
+            Some(_) => sign * ldexp(r.mantissa.to_u64() as f64, r.exponent),
+        }
+    }
+
+    /// Rounds to the nearest `f64`, ties to even.
+    #[inline]
+    pub fn to_f64(&self) -> f64 {
+        self.to_binary(53, -1074, 1023)
+    }
+
+    /// Rounds to the nearest `f32`, ties to even.
+    #[inline]
+    pub fn to_f32(&self) -> f32 {
+        self.to_binary(24, -149, 127) as f32
+    }
+}
+
+/// `x * 2^e` for a result that is representable.
+fn ldexp(mut x: f64, mut e: i64) -> f64 {
+    while e > 1000 {
+        x *= f64::from_bits((1000 + 1023) << 52);
+        e -= 1000;
+    }
+    while e < -1000 {
+        x *= f64::from_bits((1023 - 1000) << 52);
+        e += 1000;
+    }
+    x * f64::from_bits(((e + 1023) as u64) << 52)
+}
+
+impl TryFrom<f64> for BigFloat {
+    type Error = NonFiniteError;
+    fn try_from(x: f64) -> Result<Self, Self::Error> {
+        if !x.is_finite() {
+            return Err(NonFiniteError);
+        }
+        let bits = x.to_bits();
+        let (e, f) = ((bits >> 52) & 0x7ff, bits & 0x000f_ffff_ffff_ffff);
+        let (m, e) = if e == 0 {
+            (f, -1074)
+        } else {
+            (f | (1 << 52), e as i64 - 1075)
+        };
+        Ok(Self::from_parts(x.is_sign_negative(), Nat::from_u64(m), e))
+    }
+}
+
+impl TryFrom<f32> for BigFloat {
+    type Error = NonFiniteError;
+    #[inline]
+    fn try_from(x: f32) -> Result<Self, Self::Error> {
+        Self::try_from(x as f64)
+    }
+}
+
+impl TryFrom<Doubled<f32>> for BigFloat {
+    type Error = NonFiniteError;
+    /// The exact value `x.0 + x.1`.
+    #[inline]
+    fn try_from(x: Doubled<f32>) -> Result<Self, Self::Error> {
+        Ok(Self::try_from(x.0)? + Self::try_from(x.1)?)
+    }
+}
+
+impl TryFrom<Doubled<f64>> for BigFloat {
+    type Error = NonFiniteError;
+    /// The exact value `x.0 + x.1`.
+    #[inline]
+    fn try_from(x: Doubled<f64>) -> Result<Self, Self::Error> {
+        Ok(Self::try_from(x.0)? + Self::try_from(x.1)?)
+    }
+}
+
+impl Neg for BigFloat {
+    type Output = Self;
+    #[inline]
+    fn neg(self) -> Self {
+        if self.is_zero() {
+            return self;
+        }
+        Self {
+            negative: !self.negative,
+            ..self
+        }
+    }
+}
+
+impl Add for &BigFloat {
+    type Output = BigFloat;
+    fn add(self, other: Self) -> BigFloat {
+        if self.is_zero() {
+            return other.clone();
+        }
+        if other.is_zero() {
+            return self.clone();
+        }
+        let e = self.exponent.min(other.exponent);
+        let a = self.mantissa.shl(self.exponent - e);
+        let b = other.mantissa.shl(other.exponent - e);
+        if self.negative == other.negative {
+            return BigFloat::from_parts(self.negative, a.add(&b), e);
+        }
+        match a.cmp(&b) {
+            Ordering::Greater => BigFloat::from_parts(self.negative, a.sub(&b), e),
+            Ordering::Less => BigFloat::from_parts(other.negative, b.sub(&a), e),
+            Ordering::Equal => BigFloat::zero(),
+        }
+    }
+}
+
+impl Add for BigFloat {
+    type Output = Self;
+    #[inline]
+    fn add(self, other: Self) -> Self {
+        &self + &other
+    }
+}
+
+impl Sub for &BigFloat {
+    type Output = BigFloat;
+    #[inline]
+    fn sub(self, other: Self) -> BigFloat {
+        self + &(-other.clone())
+    }
+}
+
+impl Sub for BigFloat {
+    type Output = Self;
+    #[inline]
+    fn sub(self, other: Self) -> Self {
+        self + (-other)
+    }
+}
+
+impl Mul for &BigFloat {
+    type Output = BigFloat;
+    #[inline]
+    fn mul(self, other: Self) -> BigFloat {
+        BigFloat::from_parts(
+            self.negative != other.negative,
+            self.mantissa.mul(&other.mantissa),
+            self.exponent + other.exponent,
+        )
+    }
+}
+
+impl Mul for BigFloat {
+    type Output = Self;
+    #[inline]
+    fn mul(self, other: Self) -> Self {
+        &self * &other
+    }
+}
+
+impl PartialOrd for BigFloat {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigFloat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let d = self - other;
+        if d.is_zero() {
+            Ordering::Equal
+        } else if d.negative {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }
+}
+
+/// Binary formats the double-word errors are measured in.
+pub trait Format: Copy {
+    /// Significand width in bits, including the implicit bit.
+    const DIGITS: u32;
+    /// Exponent of the smallest subnormal.
+    const MIN_SUBNORMAL_EXP: i32;
+}
+
+impl Format for f32 {
+    const DIGITS: u32 = f32::MANTISSA_DIGITS;
+    const MIN_SUBNORMAL_EXP: i32 = -149;
+}
+
+impl Format for f64 {
+    const DIGITS: u32 = f64::MANTISSA_DIGITS;
+    const MIN_SUBNORMAL_EXP: i32 = -1074;
+}
+
+/// Error of `result` relative to `exact`, in ulps of the double-word format.
+///
+/// The ulp is the one of a binary format with twice the significand width of
+/// `T`, taken at `exact`: `2^(ilogb(exact) - 2 * DIGITS + 1)`, but no finer than
+/// the smallest subnormal `T`, like [`Doubled::ulp`]. A non-finite `result`
+/// gives infinity.
+pub fn ulp_error<T: Format>(result: Doubled<T>, exact: &BigFloat) -> f64
+where
+    BigFloat: TryFrom<Doubled<T>>,
+{
+    let Ok(value) = BigFloat::try_from(result) else {
+        return f64::INFINITY;
+    };
+    let diff = (&value - exact).abs();
+    let min = T::MIN_SUBNORMAL_EXP as i64;
+    let ulp = exact
+        .ilogb()
+        .map_or(min, |e| (e + 1 - 2 * T::DIGITS as i64).max(min));
+    diff.ldexp(-ulp).to_f64()
+}
@@ -0,0 +1,477 @@
+#![feature(portable_simd)]
+
+use core::simd::Simd;
+use doubled::verify::{ulp_error, BigFloat};
+use doubled::*;
+
+/// xorshift64* generator, so that the inputs are reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// `f64` with a random significand in `[1, 2)` scaled by `2^[-8, 8)`.
+    fn f64(&mut self) -> f64 {
+        let bits = self.next_u64();
+        let m = f64::from_bits(0x3ff0_0000_0000_0000 | (bits >> 12));
+        let e = (bits & 0xf) as i32 - 8;
+        let s = if bits & 0x10 == 0 { 1. } else { -1. };
+        s * m * 2f64.powi(e)
+    }
+
+    /// Normalized `Doubled<f64>` with a random low word.
+    fn doubled_f64(&mut self) -> Doubled<f64> {
+        let hi = self.f64();
+        let lo = hi * self.f64().abs() * f64::EPSILON * 0.25;
+        Doubled::new(hi, lo).normalize()
+    }
+
+    fn doubled_f32(&mut self) -> Doubled<f32> {
+        let hi = self.f64() as f32;
+        let lo = hi * self.f64().abs() as f32 * f32::EPSILON * 0.25;
+        Doubled::new(hi, lo).normalize()
+    }
+}
+
+fn big<T>(x: T) -> BigFloat
+where
+    BigFloat: TryFrom<T>,
+{
+    BigFloat::try_from(x).ok().unwrap()
+}
+
+const N: usize = 1000;
+
+#[test]
+fn big_float_round_trip() {
+    let mut rng = Rng(1);
+    for x in [
+        0.,
+        -0.,
+        1.,
+        -2.5,
+        f64::MAX,
+        f64::MIN_POSITIVE,
+        5e-324,
+        -1.5e-310,
+    ] {
+        assert_eq!(big(x).to_f64(), x);
+    }
+    for _ in 0..N {
+        let x = rng.f64();
+        assert_eq!(big(x).to_f64(), x);
+        assert_eq!(big(x as f32).to_f32(), x as f32);
+    }
+}
+
+#[test]
+fn big_float_div_sqrt() {
+    let mut rng = Rng(2);
+    for _ in 0..N {
+        let (a, b) = (big(rng.f64()), big(rng.f64()));
+        let q = a.div(&b, 200);
+        let r = (&(&q * &b) - &a).abs();
+        assert!(r <= a.abs().ldexp(-199));
+        let a = a.abs();
+        let s = a.sqrt(200);
+        let r = (&(&s * &s) - &a).abs();
+        assert!(r <= a.ldexp(-198));
+    }
+}
+
+#[test]
+fn eft_is_exact() {
+    let mut rng = Rng(3);
+    for _ in 0..N {
+        let (a, b, c) = (rng.f64(), rng.f64() * 1e-5, rng.f64() * 1e5);
+        let (ea, eb, ec) = (big(a), big(b), big(c));
+        let sum = &ea + &eb;
+        let prod = &ea * &eb;
+        assert_eq!(big(eft::two_sum(b, a)), sum);
+        assert_eq!(big(eft::fast_two_sum(a, b)), sum);
+        assert_eq!(big(eft::two_prod_split(a, b)), prod);
+        assert_eq!(big(eft::two_prod_fma(a, b)), prod);
+        let (s, e0, e1) = eft::three_sum(a, b, c);
+        assert_eq!(&big(Doubled::new(s, e0)) + &big(e1), &sum + &ec);
+        let (q, r) = eft::two_div(a, b);
+        assert_eq!(&(&big(q) * &eb) + &big(r), ea);
+        let (s, r) = eft::two_sqrt(a.abs());
+        assert_eq!(&(&big(s) * &big(s)) + &big(r), ea.abs());
+    }
+}
+
+#[test]
+fn doubled_f64() {
+    let mut rng = Rng(4);
+    for _ in 0..N {
+        let (a, b) = (rng.doubled_f64(), rng.doubled_f64());
+        let (ea, eb) = (big(a), big(b));
+        let one = big(1f64);
+        assert!(ulp_error(a.abs() + b.abs(), &(&ea.abs() + &eb.abs())) <= 4.);
+        assert!(ulp_error(a * b, &(&ea * &eb)) <= 4.);
+        assert!(ulp_error(a / b, &ea.div(&eb, 250)) <= 6.);
+        assert!(ulp_error(b.recip(), &one.div(&eb, 250)) <= 4.);
+        assert!(ulp_error(a.square(), &(&ea * &ea)) <= 4.);
+    }
+}
+
+#[test]
+fn doubled_f32() {
+    let mut rng = Rng(5);
+    for _ in 0..N {
+        let (a, b) = (rng.doubled_f32(), rng.doubled_f32());
+        let (ea, eb) = (big(a), big(b));
+        let one = big(1f32);
+        assert!(ulp_error(a.abs() + b.abs(), &(&ea.abs() + &eb.abs())) <= 4.);
+        assert!(ulp_error(a * b, &(&ea * &eb)) <= 4.);
+        assert!(ulp_error(a / b, &ea.div(&eb, 250)) <= 6.);
+        assert!(ulp_error(b.recip(), &one.div(&eb, 250)) <= 4.);
+        assert!(ulp_error(a.square(), &(&ea * &ea)) <= 4.);
+    }
+}
+
+#[test]
+fn doubled_f64x() {
+    let mut rng = Rng(6);
+    for _ in 0..N / 4 {
+        let a = Doubled::<Simd<f64, 4>>::from_array(core::array::from_fn(|_| rng.doubled_f64()));
+        let b = Doubled::<Simd<f64, 4>>::from_array(core::array::from_fn(|_| rng.doubled_f64()));
+        let (p, q, s) = (a * b, a / b, a.abs().sqrt());
+        for i in 0..4 {
+            let (ea, eb) = (big(a.extract(i)), big(b.extract(i)));
+            assert!(ulp_error(p.extract(i), &(&ea * &eb)) <= 4.);
+            assert!(ulp_error(q.extract(i), &ea.div(&eb, 250)) <= 6.);
+            assert!(ulp_error(s.extract(i), &ea.abs().sqrt(250)) <= 8.);
+        }
+    }
+}
+
+#[test]
+fn doubled_f32x() {
+    let mut rng = Rng(7);
+    for _ in 0..N / 8 {
+        let a = Doubled::<Simd<f32, 8>>::from_array(core::array::from_fn(|_| rng.doubled_f32()));
+        let b = Doubled::<Simd<f32, 8>>::from_array(core::array::from_fn(|_| rng.doubled_f32()));
+        let (p, q, s) = (a * b, a / b, a.abs().sqrt());
+        for i in 0..8 {
+            let (ea, eb) = (big(a.extract(i)), big(b.extract(i)));
+            assert!(ulp_error(p.extract(i), &(&ea * &eb)) <= 4.);
+            assert!(ulp_error(q.extract(i), &ea.div(&eb, 250)) <= 6.);
+            assert!(ulp_error(s.extract(i), &ea.abs().sqrt(250)) <= 8.);
+        }
+    }
+}
+
+#[test]
+fn next_up_down_step_one_ulp() {
+    let mut rng = Rng(8);
+    for _ in 0..N {
+        let x = rng.doubled_f64();
+        let ex = big(x);
+        assert_eq!(ulp_error(x.next_up(), &ex), 1.);
+        assert_eq!(ulp_error(x.next_down(), &ex), 1.);
+        assert!(big(x.next_up()) > ex && big(x.next_down()) < ex);
+        assert_eq!(x.next_up().ulp_distance(x), 1.);
+    }
+    let one = Doubled::new(1f64, 0.);
+    assert_eq!(one.next_down().ulp(), one.ulp() * 0.5);
+    assert_eq!(one.next_down().next_up(), one);
+}
+
+#[test]
+fn full_range_f64() {
+    let mut rng = Rng(9);
+    let scaled = |rng: &mut Rng| {
+        let x = rng.doubled_f64();
+        let s = 2f64.powi((rng.next_u64() % 2001) as i32 - 1000);
+        Doubled::new(x.0 * s, x.1 * s)
+    };
+    let fits = |e: &BigFloat| e.ilogb().is_none_or(|e| e < 1023);
+    for _ in 0..N {
+        let (a, b) = (scaled(&mut rng), scaled(&mut rng));
+        let (ea, eb) = (big(a), big(b));
+        let one = big(1f64);
+        let (p, q, r, s) = (&ea * &eb, ea.div(&eb, 250), one.div(&eb, 250), &ea * &ea);
+        let m = &big(a.0) * &big(b.0);
+        if fits(&p) {
+            assert!(ulp_error(a.mul_full_range(b), &p) <= 4.);
+        }
+        if fits(&q) {
+            assert!(ulp_error(a.div_full_range(b), &q) <= 6.);
+        }
+        if fits(&r) {
+            assert!(ulp_error(b.recip_full_range(), &r) <= 4.);
+        }
+        if fits(&s) {
+            assert!(ulp_error(a.square_full_range(), &s) <= 4.);
+        }
+        if fits(&m) {
+            assert!(ulp_error(a.0.mul_as_doubled_full_range(b.0), &m) <= 4.);
+        }
+    }
+}
+
+#[test]
+fn full_range_f32() {
+    let mut rng = Rng(10);
+    let scaled = |rng: &mut Rng| {
+        let x = rng.doubled_f32();
+        let s = 2f32.powi((rng.next_u64() % 241) as i32 - 120);
+        Doubled::new(x.0 * s, x.1 * s)
+    };
+    let fits = |e: &BigFloat| e.ilogb().is_none_or(|e| e < 127);
+    for _ in 0..N {
+        let (a, b) = (scaled(&mut rng), scaled(&mut rng));
+        let (ea, eb) = (big(a), big(b));
+        let one = big(1f32);
+        let (p, q, r, s) = (&ea * &eb, ea.div(&eb, 250), one.div(&eb, 250), &ea * &ea);
+        let m = &big(a.0) * &big(b.0);
+        if fits(&p) {
+            assert!(ulp_error(a.mul_full_range(b), &p) <= 4.);
+        }
+        if fits(&q) {
+            assert!(ulp_error(a.div_full_range(b), &q) <= 6.);
+        }
+        if fits(&r) {
+            assert!(ulp_error(b.recip_full_range(), &r) <= 4.);
+        }
+        if fits(&s) {
+            assert!(ulp_error(a.square_full_range(), &s) <= 4.);
+        }
+        if fits(&m) {
+            assert!(ulp_error(a.0.mul_as_doubled_full_range(b.0), &m) <= 4.);
+        }
+    }
+}
+
+#[test]
+fn full_range_f64x() {
+    let mut rng = Rng(11);
+    let scaled = |rng: &mut Rng| {
+        let x = rng.doubled_f64();
+        let s = 2f64.powi((rng.next_u64() % 2001) as i32 - 1000);
+        Doubled::new(x.0 * s, x.1 * s)
+    };
+    let fits = |e: &BigFloat| e.ilogb().is_none_or(|e| e < 1023);
+    for _ in 0..N / 4 {
+        let a = Doubled::<Simd<f64, 4>>::from_array(core::array::from_fn(|_| scaled(&mut rng)));
+        let b = Doubled::<Simd<f64, 4>>::from_array(core::array::from_fn(|_| scaled(&mut rng)));
+        let (p, q) = (a.mul_full_range(b), a.div_full_range(b));
+        for i in 0..4 {
+            let (ea, eb) = (big(a.extract(i)), big(b.extract(i)));
+            let (ep, eq) = (&ea * &eb, ea.div(&eb, 250));
+            if fits(&ep) {
+                assert!(ulp_error(p.extract(i), &ep) <= 4.);
+            }
+            if fits(&eq) {
+                assert!(ulp_error(q.extract(i), &eq) <= 6.);
+            }
+        }
+    }
+}
+
+#[test]
+fn full_range_f32x() {
+    let mut rng = Rng(12);
+    let scaled = |rng: &mut Rng| {
+        let x = rng.doubled_f32();
+        let s = 2f32.powi((rng.next_u64() % 241) as i32 - 120);
+        Doubled::new(x.0 * s, x.1 * s)
+    };
+    let fits = |e: &BigFloat| e.ilogb().is_none_or(|e| e < 127);
+    for _ in 0..N / 8 {
+        let a = Doubled::<Simd<f32, 8>>::from_array(core::array::from_fn(|_| scaled(&mut rng)));
+        let b = Doubled::<Simd<f32, 8>>::from_array(core::array::from_fn(|_| scaled(&mut rng)));
+        let (p, q) = (a.mul_full_range(b), a.div_full_range(b));
+        for i in 0..8 {
+            let (ea, eb) = (big(a.extract(i)), big(b.extract(i)));
+            let (ep, eq) = (&ea * &eb, ea.div(&eb, 250));
+            if fits(&ep) {
+                assert!(ulp_error(p.extract(i), &ep) <= 4.);
+            }
+            if fits(&eq) {
+                assert!(ulp_error(q.extract(i), &eq) <= 6.);
+            }
+        }
+    }
+}
+
+/// Results in the subnormal range are rounded once from the double-word
+/// value, so they are correctly rounded.
+#[test]
+fn full_range_into_subnormals() {
+    let a = Doubled::new(1.5 * 2f64.powi(-537), -2f64.powi(-600));
+    let b = Doubled::new(2f64.powi(-537), 0.);
+    assert_eq!(a.mul_full_range(b), Doubled::new(f64::from_bits(1), 0.));
+    let v = Doubled::<Simd<f64, 4>>::splat(a).mul_full_range(Doubled::<Simd<f64, 4>>::splat(b));
+    assert_eq!(v.extract(0), Doubled::new(f64::from_bits(1), 0.));
+
+    let mut rng = Rng(17);
+    let subnormal = |e: &BigFloat, min: i64| e.ilogb().is_some_and(|e| e < min);
+    for _ in 0..N {
+        // Products, quotients and squares near 2^-1050, and reciprocals of
+        // values in [2^1022, 2^1023).
+        let (x, y) = (rng.doubled_f64(), rng.doubled_f64());
+        let k = (rng.next_u64() % 1000) as i32;
+        let (a, b) = (x.ldexp(-1050 + k), y.ldexp(-k));
+        let (c, d) = (x.ldexp(-1050 + k - 1000), y.ldexp(k - 1000));
+        let (z, w) = (y.ldexp(1022 - y.ilogb()), x.ldexp(-525));
+        let (ea, eb, ec, ed, ez, ew) = (big(a), big(b), big(c), big(d), big(z), big(w));
+        let cases = [
+            (a.mul_full_range(b), &ea * &eb),
+            (c.div_full_range(d), ec.div(&ed, 250)),
+            (z.recip_full_range(), big(1f64).div(&ez, 250)),
+            (w.square_full_range(), &ew * &ew),
+            (a.0.mul_as_doubled_full_range(b.0), &big(a.0) * &big(b.0)),
+        ];
+        for (r, e) in cases {
+            if subnormal(&e, -1022) {
+                assert!(ulp_error(r, &e) <= 0.5, "{r:?}");
+            }
+        }
+
+        // The same near 2^-140 and 2^126 for `f32`.
+        let (x, y) = (rng.doubled_f32(), rng.doubled_f32());
+        let k = (rng.next_u64() % 100) as i32;
+        let (a, b) = (x.ldexp(-140 + k), y.ldexp(-k));
+        let (c, d) = (x.ldexp(-140 + k - 100), y.ldexp(k - 100));
+        let (z, w) = (y.ldexp(126 - y.ilogb()), x.ldexp(-70));
+        let (ea, eb, ec, ed, ez, ew) = (big(a), big(b), big(c), big(d), big(z), big(w));
+        let cases = [
+            (a.mul_full_range(b), &ea * &eb),
+            (c.div_full_range(d), ec.div(&ed, 250)),
+            (z.recip_full_range(), big(1f32).div(&ez, 250)),
+            (w.square_full_range(), &ew * &ew),
+            (a.0.mul_as_doubled_full_range(b.0), &big(a.0) * &big(b.0)),
+        ];
+        for (r, e) in cases {
+            if subnormal(&e, -126) {
+                assert!(ulp_error(r, &e) <= 0.5, "{r:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn ldexp_frexp() {
+    let mut rng = Rng(13);
+    for _ in 0..N {
+        let x = rng.doubled_f64();
+        let e = (rng.next_u64() % 2201) as i32 - 1150;
+        let exact = big(x).ldexp(e as i64);
+        if exact.ilogb().is_some_and(|e| e < 1023) {
+            assert!(ulp_error(x.ldexp(e), &exact) <= 0.5, "{x:?} {e}");
+        }
+        let y = x.ldexp(e);
+        if y.0 != 0. && y.0.is_finite() {
+            let (m, k) = y.frexp();
+            assert_eq!(Some(y.ilogb() as i64), big(y).ilogb());
+            assert!((0.5..1.).contains(&(m.0 + m.1).abs()));
+            assert_eq!(big(m.ldexp(k)), big(y));
+        }
+    }
+}
+
+/// Scaling into the subnormal range must round once, also when the input is
+/// itself close to it.
+#[test]
+fn ldexp_into_subnormals() {
+    let x = Doubled::new(f64::from_bits(0x0117_adcd_82d7_ffc3), 0.);
+    assert!(ulp_error(x.ldexp(-36), &big(x).ldexp(-36)) <= 0.5);
+    // The scaled residual is an exact tie that only the rest of the low word
+    // breaks.
+    let x = Doubled::new(1.5f64, -2f64.powi(-63));
+    assert_eq!(x.ldexp(-1074), Doubled::new(f64::from_bits(1), 0.));
+    let v = Doubled::<Simd<f64, 4>>::splat(x).ldexp(Simd::splat(-1074));
+    assert_eq!(v.extract(0), x.ldexp(-1074));
+    let x = Doubled::new(1.5f32, -2f32.powi(-30));
+    assert!(ulp_error(x.ldexp(-149), &big(x).ldexp(-149)) <= 0.5);
+    let v = Doubled::<Simd<f32, 8>>::splat(x).ldexp(Simd::splat(-149));
+    assert_eq!(v.extract(0), x.ldexp(-149));
+
+    let mut rng = Rng(16);
+    for _ in 0..N {
+        // Near 2^-1000, scaled to 2^-1070 and below.
+        let x = rng.doubled_f64() * 2f64.powi(-1000);
+        let e = -((rng.next_u64() % 90) as i32);
+        let exact = big(x).ldexp(e as i64);
+        assert!(ulp_error(x.ldexp(e), &exact) <= 0.5, "{x:?} {e}");
+        let v = Doubled::<Simd<f64, 4>>::splat(x).ldexp(Simd::splat(e));
+        assert_eq!(v.extract(0), x.ldexp(e));
+
+        let x = rng.doubled_f32() * 2f32.powi(-110);
+        let e = -((rng.next_u64() % 50) as i32);
+        let exact = big(x).ldexp(e as i64);
+        assert!(ulp_error(x.ldexp(e), &exact) <= 0.5, "{x:?} {e}");
+        let v = Doubled::<Simd<f32, 8>>::splat(x).ldexp(Simd::splat(e));
+        assert_eq!(v.extract(0), x.ldexp(e));
+    }
+}
+
+#[test]
+fn ldexp_frexp_simd() {
+    let mut rng = Rng(14);
+    for _ in 0..N / 4 {
+        let x = Doubled::<Simd<f64, 4>>::from_array(core::array::from_fn(|_| rng.doubled_f64()));
+        let e = Simd::from_array(core::array::from_fn(|_| {
+            (rng.next_u64() % 2201) as i32 - 1150
+        }));
+        let (y, (m, k)) = (x.ldexp(e), x.frexp());
+        for i in 0..4 {
+            assert_eq!(y.extract(i), x.extract(i).ldexp(e[i]));
+            assert_eq!((m.extract(i), k[i]), x.extract(i).frexp());
+        }
+    }
+    for _ in 0..N / 8 {
+        let x = Doubled::<Simd<f32, 8>>::from_array(core::array::from_fn(|_| rng.doubled_f32()));
+        let e = Simd::from_array(core::array::from_fn(|_| {
+            (rng.next_u64() % 301) as i32 - 160
+        }));
+        let (y, (m, k)) = (x.ldexp(e), x.frexp());
+        for i in 0..8 {
+            assert_eq!(y.extract(i), x.extract(i).ldexp(e[i]));
+            assert_eq!((m.extract(i), k[i]), x.extract(i).frexp());
+        }
+    }
+}
+
+#[test]
+fn mixed_precision_conversions() {
+    let mut rng = Rng(15);
+    for _ in 0..N {
+        let x = rng.doubled_f64();
+        let y: Doubled<f32> = x.into();
+        assert!(ulp_error(y, &big(x)) <= 1.);
+        let z = rng.doubled_f32();
+        assert_eq!(big(Doubled::<f64>::from(z)), big(z));
+        assert_eq!(z.to_f64(), big(z).to_f64());
+    }
+    let xs: [Doubled<f64>; 4] = core::array::from_fn(|_| rng.doubled_f64());
+    let v: Doubled<Simd<f32, 4>> = Doubled::<Simd<f64, 4>>::from_array(xs).into();
+    let w: Doubled<Simd<f64, 4>> = v.into();
+    for (i, &x) in xs.iter().enumerate() {
+        assert_eq!(v.extract(i), Doubled::<f32>::from(x));
+        assert_eq!(w.extract(i), Doubled::<f64>::from(v.extract(i)));
+    }
+}
+
+/// The rest below the `f32` high word may fall on a tie of the low word in
+/// `f64`, where the sign of the `f64` low word decides.
+#[test]
+fn narrowing_rounds_the_rest_once() {
+    // The rest is 2^-25 + 2^-49, halfway between two `f32`.
+    let hi = 1. + 2f64.powi(-25) + 2f64.powi(-49);
+    let up = Doubled::new(1f32, 2f32.powi(-25) + 2f32.powi(-48));
+    let down = Doubled::new(1f32, 2f32.powi(-25));
+    for (lo, expected) in [(2f64.powi(-80), up), (-(2f64.powi(-80)), down)] {
+        let x = Doubled::new(hi, lo);
+        assert_eq!(Doubled::<f32>::from(x), expected, "{lo}");
+        let v: Doubled<Simd<f32, 4>> = Doubled::<Simd<f64, 4>>::splat(x).into();
+        assert_eq!(v.extract(3), expected, "{lo}");
+    }
+}
@@ -0,0 +1,84 @@
+#![feature(portable_simd)]
+
+use core::simd::Simd;
+use doubled::base::DoubledBase;
+use doubled::*;
+
+/// `hypot(x, y) * |y|`, written once for scalars and vectors.
+fn norm<T: DoubledBase>(x: Doubled<T>, y: Doubled<T>) -> Doubled<T> {
+    (x.square() + y.square()).sqrt() / y.abs().recip()
+}
+
+#[test]
+fn generic_matches_scalar_lanes() {
+    let xs = [1.5, -2.25, 3e-3, 7.];
+    let ys = [0.5, 4., -1e3, 1. / 3.];
+    let x = Doubled::<Simd<f64, 4>>::from_array(xs.map(Doubled::from));
+    let y = Doubled::<Simd<f64, 4>>::from_array(ys.map(Doubled::from));
+    let r = norm(x, y);
+    for i in 0..4 {
+        let (v, s) = (
+            r.extract(i),
+            norm(Doubled::from(xs[i]), Doubled::from(ys[i])),
+        );
+        if <Simd<f64, 4>>::FMA {
+            // Vectors use FMA on such targets, scalars never do.
+            assert_eq!(v.0, s.0);
+            assert!((v.1 - s.1).abs() <= 1e-30 * s.0.abs());
+        } else {
+            assert_eq!(v, s);
+        }
+    }
+    let r = norm(Doubled::<f32>::from(3f32), Doubled::from(4f32)).mul_full_range(Doubled::from(2.));
+    assert!((r.0 - 40.).abs() + r.1.abs() < 1e-12);
+}
+
+fn bits64(d: Doubled<f64>) -> (u64, u64) {
+    (d.0.to_bits(), d.1.to_bits())
+}
+
+fn bits32(d: Doubled<f32>) -> (u32, u32) {
+    (d.0.to_bits(), d.1.to_bits())
+}
+
+/// The scalar operations use Dekker's products on every target, so their
+/// results are pinned to the bits they have without FMA.
+#[test]
+fn scalar_results_are_target_independent() {
+    let x = Doubled::new(0.1f64, -5.551115123125783e-18);
+    let y = Doubled::new(-2. / 3., 3.700743415417188e-17);
+    let (a, b) = (1.1f64, -7.3f64);
+    assert_eq!(bits64(x * y), (0xbfb1111111111111, 0x3c5dddddddddddde));
+    assert_eq!(bits64(x * b), (0xbfe75c28f5c28f5c, 0));
+    assert_eq!(bits64(x / y), (0xbfc3333333333334, 0x3c59999999999998));
+    assert_eq!(bits64(y.recip()), (0xbff8000000000000, 0xbca8000000000000));
+    assert_eq!(bits64(y.square()), (0x3fdc71c71c71c71c, 0xbc95555555555555));
+    assert_eq!(y.square_as_f().to_bits(), 0x3fdc71c71c71c71b);
+    assert_eq!(x.mul_as_f(y).to_bits(), 0xbfb1111111111111);
+    assert_eq!(
+        bits64(a.mul_as_doubled(b)),
+        (0xc0200f5c28f5c290, 0x3cc8a3d70a3d70a4)
+    );
+    assert_eq!(
+        bits64(b.recip_as_doubled()),
+        (0xbfc188c46231188c, 0xbc69387224077e7c)
+    );
+    // A negative zero high word is not negative, so it is left as is.
+    let z = Doubled::new(-0f64, 1e-300);
+    assert_eq!(bits64(z.abs()), bits64(z));
+
+    let x = Doubled::new(0.1f32, 1.4901161e-9);
+    let y = Doubled::new(-2f32 / 3., -1.9868216e-8);
+    let (a, b) = (1.1f32, -7.3f32);
+    assert_eq!(bits32(x * y), (0xbd888889, 0xb12aaaac));
+    assert_eq!(bits32(x * b), (0xbf3ae148, 0xb2bae148));
+    assert_eq!(bits32(x / y), (0xbe19999a, 0x32333334));
+    assert_eq!(bits32(y.recip()), (0xbfc00000, 0x33c00000));
+    assert_eq!(bits32(y.square()), (0x3ee38e3a, 0x32aaaaac));
+    assert_eq!(y.square_as_f().to_bits(), 0x3ee38e3b);
+    assert_eq!(x.mul_as_f(y).to_bits(), 0xbd888889);
+    assert_eq!(bits32(a.mul_as_doubled(b)), (0xc1007ae2, 0x34a28f5c));
+    assert_eq!(bits32(b.recip_as_doubled()), (0xbe0c4623, 0x312fd2a6));
+    let z = Doubled::new(-0f32, 1e-30);
+    assert_eq!(bits32(z.abs()), bits32(z));
+}
@@ -0,0 +1,111 @@
+#![feature(portable_simd)]
+
+use core::simd::Simd;
+use doubled::*;
+
+type F64x = Simd<f64, 4>;
+type F32x = Simd<f32, 8>;
+
+/// Larger than `small` in magnitude in every lane but lane 2.
+fn large() -> F64x {
+    F64x::from_array([4., -4., 0.5, 4.])
+}
+
+fn small() -> F64x {
+    F64x::from_array([1., 1., -2., 1.])
+}
+
+fn doubled(x: F64x) -> Doubled<F64x> {
+    Doubled::new(x, F64x::splat(0.))
+}
+
+#[test]
+fn ordered_operands_pass() {
+    let (a, b) = (large(), F64x::from_array([1., 1., 0.25, 1.]));
+    a.check_order(b);
+    let s = doubled(a).add_checked(doubled(b));
+    assert_eq!(s.0.to_array(), [5., -3., 0.75, 5.]);
+    // Infinite and NaN lanes are not checked.
+    F64x::from_array([1., f64::INFINITY, f64::NAN, 1.])
+        .check_order(F64x::from_array([0.5, 2., 3., -1.]));
+}
+
+#[cfg(debug_assertions)]
+mod debug {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "check_order failed in lane 2: |0.5| < |-2|")]
+    fn check_order() {
+        large().check_order(small());
+    }
+
+    #[test]
+    #[should_panic(expected = "check_order failed in lane 2")]
+    fn add_checked() {
+        doubled(large()).add_checked(doubled(small()));
+    }
+
+    #[test]
+    #[should_panic(expected = "check_order failed in lane 2")]
+    fn add_checked_scalar() {
+        doubled(large()).add_checked(small());
+    }
+
+    #[test]
+    #[should_panic(expected = "check_order failed in lane 2")]
+    fn scalar_add_checked() {
+        large().add_checked(doubled(small()));
+    }
+
+    #[test]
+    #[should_panic(expected = "check_order failed in lane 2")]
+    fn add_checked_assign() {
+        let mut x = doubled(large());
+        x.add_checked_assign(doubled(small()));
+    }
+
+    #[test]
+    #[should_panic(expected = "check_order failed in lane 2")]
+    fn add_checked_assign_scalar() {
+        let mut x = doubled(large());
+        x.add_checked_assign(small());
+    }
+
+    #[test]
+    #[should_panic(expected = "check_order failed in lane 2")]
+    fn sub_checked() {
+        doubled(large()).sub_checked(doubled(small()));
+    }
+
+    #[test]
+    #[should_panic(expected = "check_order failed in lane 2")]
+    fn sub_checked_scalar() {
+        doubled(large()).sub_checked(small());
+    }
+
+    #[test]
+    #[should_panic(expected = "check_order failed in lane 2")]
+    fn add_checked_as_doubled() {
+        large().add_checked_as_doubled(small());
+    }
+
+    #[test]
+    #[should_panic(expected = "check_order failed in lane 7: |1| < |2|")]
+    fn f32_lane() {
+        let mut a = F32x::splat(4.);
+        a[7] = 1.;
+        a.add_checked_as_doubled(F32x::splat(2.));
+    }
+}
+
+/// The checks are compiled out of release builds, run with
+/// `cargo test --release`.
+#[cfg(not(debug_assertions))]
+#[test]
+fn unchecked_in_release() {
+    large().check_order(small());
+    let s = doubled(large()).add_checked(doubled(small()));
+    assert_eq!(s.0[2], -1.5);
+    F32x::splat(1.).add_checked_as_doubled(F32x::splat(2.));
+}
@@ -0,0 +1,8 @@
+//! Helpers shared by the integration tests.
+
+use doubled::Doubled;
+
+/// `|x|`, rounded to `f64`.
+pub fn abs(x: Doubled<f64>) -> f64 {
+    (x.0 + x.1).abs()
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::abs;
+use doubled::fft::{root_of_unity, Plan};
+use doubled::{Doubled, Normalize};
+
+type D = Doubled<f64>;
+
+fn signal(n: usize) -> (Vec<D>, Vec<D>) {
+    let x = |i: usize, s: f64| {
+        D::from(1.) / D::from((i as f64 + s) * if i.is_multiple_of(3) { -1. } else { 1. })
+    };
+    (
+        (0..n).map(|i| x(i, 1.)).collect(),
+        (0..n).map(|i| x(i, 0.5)).collect(),
+    )
+}
+
+/// Direct `O(n²)` transform.
+fn dft(re: &[D], im: &[D]) -> (Vec<D>, Vec<D>) {
+    let n = re.len();
+    (0..n)
+        .map(|f| {
+            (0..n).fold((D::from(0.), D::from(0.)), |(sr, si), t| {
+                let (c, s) = root_of_unity(f * t, n);
+                // (re + i im)(c - i s)
+                (
+                    (sr + re[t] * c + im[t] * s).normalize(),
+                    (si + im[t] * c - re[t] * s).normalize(),
+                )
+            })
+        })
+        .unzip()
+}
+
+#[test]
+fn roots_of_unity() {
+    for n in [1, 3, 7, 8, 12, 1000] {
+        for k in 0..2 * n {
+            let (c, s) = root_of_unity(k, n);
+            assert!(
+                abs(c.square() + s.square() - D::from(1.)) < 1e-31,
+                "{k}/{n}"
+            );
+        }
+    }
+    assert_eq!(root_of_unity(3, 12), (D::from(0.), D::from(1.)));
+    assert_eq!(root_of_unity(6, 12), (D::from(-1.), D::from(0.)));
+    let (c, s) = root_of_unity(1, 8);
+    assert_eq!(c, s);
+    assert!(abs(c.square() - D::from(0.5)) < 1e-31);
+    let (c, s) = root_of_unity(1, 12);
+    assert!(abs(s - D::from(0.5)) < 1e-31);
+    assert!(abs(c.square() - D::from(0.75)) < 1e-31);
+}
+
+#[test]
+fn matches_direct_transform() {
+    for n in [1, 2, 4, 7, 8, 30, 48, 64, 100] {
+        let (re, im) = signal(n);
+        let (er, ei) = dft(&re, &im);
+        let plan = Plan::new(n);
+        assert_eq!(plan.size(), n);
+        let (mut xr, mut xi) = (re.clone(), im.clone());
+        plan.forward(&mut xr, &mut xi);
+        for f in 0..n {
+            assert!(abs(xr[f] - er[f]) + abs(xi[f] - ei[f]) < 1e-29, "{n} {f}");
+        }
+        plan.inverse(&mut xr, &mut xi);
+        for t in 0..n {
+            assert!(abs(xr[t] - re[t]) + abs(xi[t] - im[t]) < 1e-30, "{n} {t}");
+        }
+    }
+}
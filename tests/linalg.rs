@@ -0,0 +1,163 @@
+use doubled::linalg::{self, Error};
+use doubled::{Doubled, Normalize};
+
+type D = Doubled<f64>;
+
+/// The Hilbert matrix `1 / (i + j + 1)`, whose condition number for `n = 10`
+/// is about `1.6e13`.
+fn hilbert(m: usize, n: usize) -> Vec<D> {
+    (0..m * n)
+        .map(|k| D::from(1.) / D::from((k / n + k % n + 1) as f64))
+        .collect()
+}
+
+/// `A x` for `x = [1, 2, ..., n]`.
+fn rhs(a: &[D], m: usize, n: usize) -> Vec<D> {
+    (0..m)
+        .map(|i| {
+            (0..n).fold(D::from(0.), |s, j| {
+                (s + a[i * n + j] * D::from((j + 1) as f64)).normalize()
+            })
+        })
+        .collect()
+}
+
+fn max_error(x: &[D]) -> f64 {
+    x.iter()
+        .enumerate()
+        .map(|(j, &x)| {
+            let e = x - D::from((j + 1) as f64);
+            (e.0 + e.1).abs()
+        })
+        .fold(0., f64::max)
+}
+
+const N: usize = 10;
+
+#[test]
+fn lu_solves_hilbert() {
+    let mut a = hilbert(N, N);
+    let mut b = rhs(&a, N, N);
+    let mut piv = [0; N];
+    linalg::lu_factor(&mut a, N, &mut piv).unwrap();
+    linalg::lu_solve(&a, N, &piv, &mut b);
+    assert!(max_error(&b) < 1e-17, "{}", max_error(&b));
+}
+
+#[test]
+fn cholesky_solves_hilbert() {
+    let mut a = hilbert(N, N);
+    let mut b = rhs(&a, N, N);
+    linalg::cholesky_factor(&mut a, N).unwrap();
+    linalg::cholesky_solve(&a, N, &mut b);
+    assert!(max_error(&b) < 1e-17, "{}", max_error(&b));
+}
+
+#[test]
+fn qr_solves_hilbert_and_least_squares() {
+    let mut a = hilbert(N, N);
+    let mut b = rhs(&a, N, N);
+    let mut tau = [D::from(0.); N];
+    linalg::qr_factor(&mut a, N, N, &mut tau);
+    linalg::qr_solve(&a, N, N, &tau, &mut b).unwrap();
+    assert!(max_error(&b) < 1e-17, "{}", max_error(&b));
+
+    // A consistent overdetermined system has a zero residual.
+    let (m, n) = (N + 4, 6);
+    let mut a = hilbert(m, n);
+    let mut b = rhs(&a, m, n);
+    linalg::qr_factor(&mut a, m, n, &mut tau);
+    linalg::qr_solve(&a, m, n, &tau, &mut b).unwrap();
+    assert!(max_error(&b[..n]) < 1e-20, "{}", max_error(&b[..n]));
+    assert!(b[n..].iter().all(|r| (r.0 + r.1).abs() < 1e-28));
+}
+
+#[test]
+fn singular_and_indefinite() {
+    let mut a = [1., 2., 2., 4.].map(D::from);
+    assert_eq!(
+        linalg::lu_factor(&mut a, 2, &mut [0; 2]),
+        Err(Error::Singular)
+    );
+    let mut a = [1., 2., 2., 1.].map(D::from);
+    assert_eq!(
+        linalg::cholesky_factor(&mut a, 2),
+        Err(Error::NotPositiveDefinite)
+    );
+    let mut a = [1., 0., 2., 0.].map(D::from);
+    let mut tau = [D::from(0.); 2];
+    linalg::qr_factor(&mut a, 2, 2, &mut tau);
+    let mut b = [1., 1.].map(D::from);
+    assert_eq!(
+        linalg::qr_solve(&a, 2, 2, &tau, &mut b),
+        Err(Error::Singular)
+    );
+}
+
+/// Gaussian elimination with partial pivoting in `f64`, standing in for the
+/// factorization a caller would get from LAPACK.
+fn lu_f64(mut a: Vec<f64>, n: usize) -> impl FnMut(&mut [f64]) {
+    let mut piv = vec![0; n];
+    for k in 0..n {
+        let p = (k..n)
+            .max_by(|&i, &j| a[i * n + k].abs().total_cmp(&a[j * n + k].abs()))
+            .unwrap();
+        piv[k] = p;
+        for j in 0..n {
+            a.swap(k * n + j, p * n + j);
+        }
+        for i in k + 1..n {
+            a[i * n + k] /= a[k * n + k];
+            for j in k + 1..n {
+                a[i * n + j] -= a[i * n + k] * a[k * n + j];
+            }
+        }
+    }
+    move |b: &mut [f64]| {
+        for (k, &p) in piv.iter().enumerate() {
+            b.swap(k, p);
+        }
+        for i in 0..n {
+            for j in 0..i {
+                b[i] -= a[i * n + j] * b[j];
+            }
+        }
+        for i in (0..n).rev() {
+            for j in i + 1..n {
+                b[i] -= a[i * n + j] * b[j];
+            }
+            b[i] /= a[i * n + i];
+        }
+    }
+}
+
+#[test]
+fn refine_hilbert() {
+    // Condition number about 1.5e10, so the `f64` solve alone keeps about six
+    // digits.
+    const N: usize = 8;
+    let a: Vec<f64> = (0..N * N)
+        .map(|k| 1. / (k / N + k % N + 1) as f64)
+        .collect();
+    let b: Vec<f64> = (0..N).map(|i| 1. + i as f64).collect();
+
+    // Reference: the same `f64` system solved in double-word arithmetic, itself
+    // accurate to about 1e-21.
+    let mut lu: Vec<D> = a.iter().map(|&a| D::from(a)).collect();
+    let mut exact: Vec<D> = b.iter().map(|&b| D::from(b)).collect();
+    let mut piv = [0; N];
+    linalg::lu_factor(&mut lu, N, &mut piv).unwrap();
+    linalg::lu_solve(&lu, N, &piv, &mut exact);
+
+    let x = linalg::refine(&a, N, &b, lu_f64(a.clone(), N), 20);
+    for (x, e) in x.iter().zip(&exact) {
+        let d = *x - *e;
+        assert!((d.0 + d.1).abs() < 1e-19 * e.0.abs(), "{x:?} {e:?}");
+    }
+
+    let (mut x, mut work) = ([D::from(0.); N], [0.; N]);
+    let steps = linalg::refine_into(&a, N, &b, lu_f64(a.clone(), N), 1, &mut x, &mut work);
+    assert_eq!(steps, 1);
+    let d = x[0] - exact[0];
+    assert!((d.0 + d.1).abs() > 1e-19 * exact[0].0.abs());
+}
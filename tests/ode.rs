@@ -0,0 +1,67 @@
+use doubled::ode::{Rk4, Symplectic};
+use doubled::{Doubled, MulAsDoubled};
+
+type D = Doubled<f64>;
+
+#[test]
+fn no_round_off_drift() {
+    // With a constant derivative RK4 is exact, and after `n` steps the state
+    // is `n` times the rounded increment, to the last double-word bit.
+    let (h, n) = (0.1, 1_000_000);
+    let mut rk4 = Rk4::new(1);
+    let mut t = D::from(0.);
+    let mut y = [D::from(0.)];
+    for _ in 0..n {
+        rk4.step(|_, _, dy| dy[0] = 3., &mut t, h, &mut y);
+    }
+    let expected = (n as f64).mul_as_doubled(h / 6. * 18.);
+    assert!(
+        ((y[0] - expected).0).abs() < 1e-24,
+        "{:?} {expected:?}",
+        y[0]
+    );
+    assert!(
+        ((t - (n as f64).mul_as_doubled(h)).0).abs() < 1e-24,
+        "{t:?}"
+    );
+
+    // Plain `f64` accumulation drifts.
+    let plain = (0..n).fold(0., |y, _| y + h / 6. * 18.);
+    assert!((plain - expected.0).abs() > 1e-8);
+}
+
+#[test]
+fn rk4_order() {
+    // y' = y on [0, 1]: halving the step divides the error by 16.
+    let error = |steps: usize| {
+        let mut rk4 = Rk4::new(1);
+        let (mut t, mut y) = (D::from(0.), [D::from(1.)]);
+        for _ in 0..steps {
+            rk4.step(|_, y, dy| dy[0] = y[0], &mut t, 1. / steps as f64, &mut y);
+        }
+        (y[0].0 + y[0].1 - core::f64::consts::E).abs()
+    };
+    let ratio = error(20) / error(40);
+    assert!((15. ..17.).contains(&ratio), "{ratio}");
+}
+
+/// Energy drift of the harmonic oscillator `q'' = -q` over 1000 periods.
+fn oscillator_energy_error(mut method: Symplectic, h: f64) -> f64 {
+    let (mut q, mut p) = ([D::from(1.)], [D::from(0.)]);
+    let steps = (2000. * core::f64::consts::PI / h) as usize;
+    let mut max = 0f64;
+    for _ in 0..steps {
+        method.step(|q, a| a[0] = -q[0], h, &mut q, &mut p);
+        let (q, p) = (q[0].0 + q[0].1, p[0].0 + p[0].1);
+        max = max.max((q * q + p * p - 1.).abs());
+    }
+    max
+}
+
+#[test]
+fn symplectic_energy_is_bounded() {
+    let leapfrog = oscillator_energy_error(Symplectic::leapfrog(1), 0.01);
+    assert!(leapfrog < 3e-5, "{leapfrog:e}");
+    let yoshida = oscillator_energy_error(Symplectic::yoshida4(1), 0.01);
+    assert!(yoshida < 1e-8, "{yoshida:e}");
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::abs;
+use doubled::poly::{roots, roots_complex, Root};
+use doubled::{Doubled, Normalize};
+
+type D = Doubled<f64>;
+
+/// Coefficients of `(x - r_0)(x - r_1)...`, constant term first.
+fn from_roots(rs: &[D]) -> Vec<D> {
+    let mut c = vec![D::from(1.)];
+    for &r in rs {
+        c.insert(0, D::from(0.));
+        for i in 0..c.len() - 1 {
+            c[i] = (c[i] - r * c[i + 1]).normalize();
+        }
+    }
+    c
+}
+
+fn check_real(found: &[Root], expected: &[D], tolerance: f64) {
+    assert_eq!(found.len(), expected.len());
+    for (r, &e) in found.iter().zip(expected) {
+        let error = abs(r.re - e).hypot(abs(r.im));
+        assert!(error < tolerance, "{r:?} {e:?}");
+        assert!(error <= r.error, "{r:?} {e:?} {error:e}");
+    }
+}
+
+#[test]
+fn integer_roots() {
+    let expected: Vec<D> = (1..=10).map(|i| D::from(i as f64)).collect();
+    let coeffs: Vec<f64> = from_roots(&expected).iter().map(|c| c.0).collect();
+    let found = roots(&coeffs);
+    check_real(&found, &expected, 1e-24);
+    assert!(found.iter().all(|r| r.error < 1e-20));
+
+    // Zero roots, which are exact and sorted with the others, and leading
+    // zeros that do not raise the degree.
+    let found = roots(&[0., 0., -4., 0., 1., 0.]);
+    check_real(&found, &[-2., 0., 0., 2.].map(D::from), 1e-30);
+    for r in &found[1..3] {
+        assert_eq!((r.re, r.im, r.error), (D::from(0.), D::from(0.), 0.));
+    }
+    assert!(roots::<f64>(&[]).is_empty() && roots(&[3.]).is_empty());
+}
+
+#[test]
+fn clustered_roots() {
+    // Three roots `1e-8` apart, where `f64` errors would be of the order of
+    // the separation.
+    let one = D::from(1.);
+    let expected = [D::from(-0.5), one, one + 1e-8, one + 2e-8];
+    let found = roots(&from_roots(&expected));
+    check_real(&found, &expected, 1e-14);
+    assert!(found.iter().all(|r| r.error < 1e-12), "{found:?}");
+}
+
+#[test]
+fn complex_roots() {
+    // x² + 1
+    let found = roots(&[1., 0., 1.]);
+    assert_eq!(found.len(), 2);
+    for r in &found {
+        assert!(abs(r.re) + (abs(r.im) - 1.).abs() < 1e-29, "{r:?}");
+    }
+    assert_eq!(found[0].im.0, -found[1].im.0);
+
+    // (x - i)(x - 2 - i) = x² - (2 + 2i) x + (-1 + 2i)
+    let found = roots_complex(&[-1., -2., 1.], &[2., -2., 0.]);
+    assert_eq!(found.len(), 2);
+    for (r, re) in found.iter().zip([0., 2.]) {
+        assert!(
+            abs(r.re - D::from(re)) + abs(r.im - D::from(1.)) < 1e-29,
+            "{r:?}"
+        );
+        assert!(r.error < 1e-28);
+    }
+}
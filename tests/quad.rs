@@ -0,0 +1,128 @@
+mod common;
+
+use common::abs;
+use doubled::quad::{gauss_kronrod, gauss_legendre};
+use doubled::{Doubled, Normalize};
+
+type D = Doubled<f64>;
+
+/// `x^k`.
+fn pow(x: D, k: usize) -> D {
+    (0..k).fold(D::from(1.), |p, _| (p * x).normalize())
+}
+
+/// `∫ x^k` over `[-1, 1]`.
+fn moment(k: usize) -> D {
+    if k % 2 == 1 {
+        D::from(0.)
+    } else {
+        D::from(2.) / D::from((k + 1) as f64)
+    }
+}
+
+#[test]
+fn gauss_legendre_is_exact() {
+    for n in [1, 2, 5, 10, 20, 31] {
+        let rule = gauss_legendre(n);
+        assert_eq!(rule.nodes.len(), n);
+        for k in 0..2 * n {
+            let s = rule.integrate(-1., 1., |x| pow(x, k));
+            assert!(
+                abs(s - moment(k)) < 1e-29,
+                "{n} {k} {:e}",
+                abs(s - moment(k))
+            );
+        }
+        for i in 0..n {
+            assert_eq!(rule.nodes[i], -rule.nodes[n - 1 - i]);
+        }
+    }
+    // Known values of the 3-point rule: nodes 0, ±√(3/5), weights 8/9, 5/9.
+    let rule = gauss_legendre(3);
+    assert_eq!(rule.nodes[1], D::from(0.));
+    assert!(abs(rule.nodes[2].square() - D::from(3.) / D::from(5.)) < 1e-31);
+    assert!(abs(rule.weights[1] - D::from(8.) / D::from(9.)) < 1e-31);
+    assert_eq!(rule.nodes_f64()[2], 0.6f64.sqrt());
+    assert_eq!(rule.weights_f64()[0], 5. / 9.);
+}
+
+/// Nodes and weights of the 100-point rule against values computed with
+/// 60 significant digits.
+#[test]
+fn gauss_legendre_large_order() {
+    let rule = gauss_legendre(100);
+    let reference = [
+        (
+            99,
+            D::new(0.9997137267734413, -4.653736452734067e-17),
+            D::new(0.0007346344905056717, -7.824465602384585e-21),
+        ),
+        (
+            98,
+            D::new(0.9984919506395958, -1.9335221616344556e-17),
+            D::new(0.0017093926535181052, 4.039524446857662e-20),
+        ),
+        (
+            75,
+            D::new(0.7153381175730564, 1.526232485403318e-17),
+            D::new(0.021843002416247388, -1.1898355709863937e-18),
+        ),
+        (
+            50,
+            D::new(0.015628984421543084, -8.326696566022433e-19),
+            D::new(0.031255423453863354, 2.8702225184297684e-18),
+        ),
+    ];
+    for (i, x, w) in reference {
+        let (dx, dw) = (abs(rule.nodes[i] - x), abs(rule.weights[i] - w));
+        assert!(dx <= 1e-31 * x.0, "{i} {dx:e}");
+        assert!(dw <= 1e-29 * w.0, "{i} {dw:e}");
+        assert_eq!(rule.nodes[99 - i], -rule.nodes[i]);
+    }
+}
+
+#[test]
+fn gauss_kronrod_is_exact() {
+    for n in [1, 2, 7, 10, 15] {
+        let gk = gauss_kronrod(n).unwrap();
+        let gauss = gauss_legendre(n);
+        assert_eq!(gk.kronrod.nodes.len(), 2 * n + 1);
+        for i in 0..n {
+            assert_eq!(gk.kronrod.nodes[2 * i + 1], gauss.nodes[i]);
+        }
+        assert!(gk.kronrod.nodes.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(gk.kronrod.weights.iter().all(|w| w.0 > 0.));
+        for k in 0..=3 * n + 1 {
+            let (s, _) = gk.integrate(-1., 1., |x| pow(x, k));
+            assert!(
+                abs(s - moment(k)) < 1e-29,
+                "{n} {k} {:e}",
+                abs(s - moment(k))
+            );
+        }
+    }
+
+    // The 7-15 pair of QUADPACK.
+    let gk = gauss_kronrod(7).unwrap();
+    assert_eq!(gk.kronrod.nodes_f64()[14], 0.9914553711208126);
+    assert_eq!(gk.kronrod.weights_f64()[14], 0.022935322010529224);
+    assert_eq!(gk.kronrod.weights_f64()[7], 0.20948214108472782);
+
+    // ∫ e^x over [0, 1], with e^x by its Taylor series.
+    let exp = |x: D| {
+        let mut t = D::from(1.);
+        let mut s = t;
+        for i in 1..40 {
+            t = (t * x / D::from(i as f64)).normalize();
+            s = (s + t).normalize();
+        }
+        s
+    };
+    let (s, error) = gauss_kronrod(10).unwrap().integrate(0., 1., exp);
+    let e = (exp(D::from(1.)) - D::from(1.)).normalize();
+    assert!(
+        abs(s - e) < 1e-30 && error < 1e-20,
+        "{:e} {error:e}",
+        abs(s - e)
+    );
+}
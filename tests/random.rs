@@ -0,0 +1,101 @@
+#![feature(portable_simd)]
+
+use core::simd::Simd;
+use doubled::*;
+
+/// splitmix64, so that the draws are reproducible.
+fn bits(seed: u64) -> impl FnMut() -> u64 {
+    let mut state = seed;
+    move || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[test]
+fn f64_on_full_grid() {
+    let mut next = bits(1);
+    let mut sum = 0.;
+    for _ in 0..10_000 {
+        let x = Doubled::<f64>::random(&mut next);
+        assert!((0. ..1.).contains(&x.0) || (x.0 == 1. && x.1 < 0.));
+        assert_eq!(x, x.normalize());
+        // Both words are multiples of 2^-106.
+        assert_eq!((x.0 * 2f64.powi(106)).fract(), 0.);
+        assert_eq!((x.1 * 2f64.powi(106)).fract(), 0.);
+        sum += x.0;
+    }
+    assert!((sum / 10_000. - 0.5).abs() < 0.01);
+    assert_eq!(Doubled::<f64>::random(&mut || 0), Doubled::new(0., 0.));
+    // The largest draws stay below one, with a high word of one.
+    let max = Doubled::<f64>::random(&mut || u64::MAX);
+    assert_eq!(max, Doubled::new(1., -2f64.powi(-106)));
+    assert_eq!(max, max.normalize());
+    let mut words = [u64::MAX, 1 << 63].into_iter();
+    let x = Doubled::<f64>::random(&mut || words.next().unwrap());
+    assert_eq!(x, Doubled::new(1., -2f64.powi(-54)));
+}
+
+/// Under the largest high word, every low word keeps its value, so the tail
+/// next to one is as dense as the rest of the range.
+#[test]
+fn top_of_range_is_uniform() {
+    let mut next = bits(4);
+    let top = 1. - 2f64.powi(-53);
+    for _ in 0..10_000 {
+        let lo = next() >> 11;
+        let mut words = [u64::MAX, lo << 11].into_iter();
+        let x = Doubled::<f64>::random(&mut || words.next().unwrap());
+        assert_eq!((x.0 - top) + x.1, lo as f64 * 2f64.powi(-106));
+        assert_eq!(x.0 == 1., lo >= 1 << 52);
+    }
+    let top = 1. - 2f32.powi(-24);
+    for lo in (0..1 << 24).step_by(97) {
+        let x = Doubled::<f32>::random(&mut || 0xffff_ff00_0000_0000 | lo << 16);
+        assert_eq!((x.0 - top) + x.1, lo as f32 * 2f32.powi(-48));
+        assert_eq!(x.0 == 1., lo >= 1 << 23);
+    }
+}
+
+#[test]
+fn f32_on_full_grid() {
+    let mut next = bits(2);
+    for _ in 0..10_000 {
+        let x = Doubled::<f32>::random(&mut next);
+        assert!((0. ..1.).contains(&x.0) || (x.0 == 1. && x.1 < 0.));
+        assert_eq!(x, x.normalize());
+    }
+    // Values that round to one keep a negative low word.
+    let max = Doubled::<f32>::random(&mut || u64::MAX);
+    assert_eq!(max, Doubled::new(1., -2f32.powi(-48)));
+    assert_eq!(max, max.normalize());
+    let x = Doubled::<f32>::random(&mut || 0xffff_ff80_0000_0000);
+    assert_eq!(x, Doubled::new(1., -2f32.powi(-25)));
+    let x = Doubled::<f32>::random(&mut || 0xffff_fe80_0000_0000);
+    assert_eq!(
+        x,
+        Doubled::new(1. - 2f32.powi(-23), 2f32.powi(-25)).normalize()
+    );
+}
+
+#[test]
+fn simd_lanes_follow_scalar_draws() {
+    let (mut a, mut b) = (bits(3), bits(3));
+    for _ in 0..100 {
+        let v = Doubled::<Simd<f64, 4>>::random(&mut a);
+        let w = Doubled::<Simd<f32, 8>>::random(&mut a);
+        for i in 0..4 {
+            assert_eq!(v.extract(i), Doubled::<f64>::random(&mut b));
+        }
+        for i in 0..8 {
+            assert_eq!(w.extract(i), Doubled::<f32>::random(&mut b));
+        }
+    }
+    let v = Doubled::<Simd<f64, 4>>::random(&mut || u64::MAX);
+    let w = Doubled::<Simd<f32, 8>>::random(&mut || u64::MAX);
+    assert_eq!(v.extract(1), Doubled::<f64>::random(&mut || u64::MAX));
+    assert_eq!(w.extract(5), Doubled::<f32>::random(&mut || u64::MAX));
+}
@@ -0,0 +1,95 @@
+use doubled::remez::{minimax, Approximation, Precision, Weight};
+use doubled::{Doubled, Normalize};
+
+type D = Doubled<f64>;
+
+fn f(x: D) -> D {
+    D::from(1.) / (x + 2.)
+}
+
+/// Maximum weighted error on a uniform grid finer than the one of the fit.
+fn dense_error(p: &Approximation, weight: Weight) -> f64 {
+    (0..=20000)
+        .map(|i| {
+            let x = D::from(-1. + i as f64 / 10000.);
+            let e = (p.eval(x) - f(x)).normalize();
+            let e = (e.0 + e.1).abs();
+            match weight {
+                Weight::Absolute => e,
+                Weight::Relative => e / f(x).0,
+            }
+        })
+        .fold(0., f64::max)
+}
+
+#[test]
+fn levels_the_error() {
+    for (degree, weight) in [
+        (8, Weight::Absolute),
+        (8, Weight::Relative),
+        (5, Weight::Relative),
+    ] {
+        let p = minimax(f, -1., 1., degree, weight, &[]).unwrap();
+        assert_eq!(p.coeffs.len(), degree + 1);
+        let dense = dense_error(&p, weight);
+        assert!(
+            dense <= p.max_error * (1. + 1e-9),
+            "{dense:e} {:e}",
+            p.max_error
+        );
+        assert!(
+            dense >= p.max_error * (1. - 1e-3),
+            "{dense:e} {:e}",
+            p.max_error
+        );
+    }
+}
+
+#[test]
+fn below_f64_precision() {
+    // The error of degree 30 is about 1e-17, under the rounding of `f64`.
+    let p = minimax(f, -1., 1., 30, Weight::Absolute, &[]).unwrap();
+    assert!(
+        p.max_error < 1e-16 && p.max_error > 1e-19,
+        "{:e}",
+        p.max_error
+    );
+    assert!(dense_error(&p, Weight::Absolute) <= p.max_error * (1. + 1e-6));
+}
+
+#[test]
+fn fixed_precision() {
+    let exact = minimax(f, -1., 1., 8, Weight::Absolute, &[]).unwrap();
+    let p = minimax(f, -1., 1., 8, Weight::Absolute, &[Precision::F32; 9]).unwrap();
+    for (c, c32) in p.coeffs.iter().zip(p.to_f32()) {
+        assert_eq!(*c, D::from(c32 as f64));
+    }
+    assert!(p.max_error >= exact.max_error * (1. - 1e-9));
+    assert!(
+        p.max_error < exact.max_error * 1.1,
+        "{:e} {:e}",
+        p.max_error,
+        exact.max_error
+    );
+
+    let mut precision = [Precision::Doubled; 9];
+    precision[0] = Precision::F64;
+    let p = minimax(f, -1., 1., 8, Weight::Absolute, &precision).unwrap();
+    assert_eq!(p.coeffs[0].1, 0.);
+    assert_eq!(p.to_f64()[0], p.coeffs[0].0);
+}
+
+/// `1 + 2^-24` is halfway between two `f32`, and the low word decides.
+#[test]
+fn f32_rounding_uses_the_low_word() {
+    let tie = 1. + 2f64.powi(-24);
+    let p = Approximation {
+        coeffs: vec![D::new(tie, 2f64.powi(-80)), D::new(tie, -(2f64.powi(-80)))],
+        max_error: 0.,
+    };
+    assert_eq!(p.to_f32(), [1. + 2f32.powi(-23), 1.]);
+
+    let c = D::new(tie, 2f64.powi(-80));
+    let p = minimax(|_| c, 0., 1., 0, Weight::Absolute, &[Precision::F32]).unwrap();
+    assert_eq!(p.coeffs, [D::from(1. + 2f64.powi(-23))]);
+}
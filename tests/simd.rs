@@ -0,0 +1,287 @@
+#![feature(portable_simd)]
+
+use core::simd::{Mask, Simd};
+use doubled::*;
+
+type D4 = Doubled<Simd<f64, 4>>;
+
+fn d4(lanes: [(f64, f64); 4]) -> D4 {
+    D4::from_array(lanes.map(|(hi, lo)| Doubled::new(hi, lo)))
+}
+
+#[test]
+fn comparisons_use_the_low_word() {
+    // Equal high words in every lane but the last, so the low words decide.
+    let x = d4([(1., 1e-20), (1., -1e-20), (-2., 0.), (3., 0.)]);
+    let y = d4([(1., -1e-20), (1., -1e-20), (-2., 1e-30), (f64::NAN, 0.)]);
+    assert_eq!(x.simd_eq(y).to_array(), [false, true, false, false]);
+    assert_eq!(x.simd_ne(y).to_array(), [true, false, true, true]);
+    assert_eq!(x.simd_lt(y).to_array(), [false, false, true, false]);
+    assert_eq!(x.simd_le(y).to_array(), [false, true, true, false]);
+    assert_eq!(x.simd_gt(y).to_array(), [true, false, false, false]);
+    assert_eq!(x.simd_ge(y).to_array(), [true, true, false, false]);
+
+    // A NaN low word is enough to make the lane NaN.
+    let z = d4([
+        (1., f64::NAN),
+        (f64::NAN, 0.),
+        (f64::INFINITY, 0.),
+        (0., 0.),
+    ]);
+    assert_eq!(z.is_nan().to_array(), [true, true, false, false]);
+    assert_eq!(z.is_finite().to_array(), [false, false, false, true]);
+    assert_eq!(z.simd_eq(z).to_array(), [false, false, true, true]);
+
+    let x = Doubled::<Simd<f32, 2>>::from_array([Doubled::new(1., 1e-9), Doubled::new(1., 0.)]);
+    let y = Doubled::<Simd<f32, 2>>::from_array([Doubled::new(1., 0.), Doubled::new(1., 1e-9)]);
+    assert_eq!(x.simd_gt(y).to_array(), [true, false]);
+    assert_eq!(x.simd_lt(y).to_array(), [false, true]);
+}
+
+#[test]
+fn select_and_set_masked() {
+    let x = d4([(1., 1e-20), (2., 2e-20), (3., 3e-20), (4., 4e-20)]);
+    let y = d4([(-1., 0.), (-2., 0.), (-3., 0.), (-4., 0.)]);
+    let mask = Mask::from_array([true, false, false, true]);
+    let r = D4::select(mask, x, y);
+    assert_eq!(
+        r.to_array(),
+        [
+            Doubled::new(1., 1e-20),
+            Doubled::new(-2., 0.),
+            Doubled::new(-3., 0.),
+            Doubled::new(4., 4e-20),
+        ]
+    );
+
+    // Clamping negative lanes to zero, with the mask from a comparison.
+    let mut z = d4([(1., 1e-20), (-1., 1e-20), (0., -1e-30), (f64::NAN, 0.)]);
+    let zero = D4::splat(Doubled::new(0., 0.));
+    z.set_masked(z.simd_lt(zero) | z.is_nan(), zero);
+    assert_eq!(
+        z.to_array(),
+        [
+            Doubled::new(1., 1e-20),
+            Doubled::new(0., 0.),
+            Doubled::new(0., 0.),
+            Doubled::new(0., 0.),
+        ]
+    );
+}
+
+#[test]
+fn array_conversions_and_lanes() {
+    let lanes = [
+        Doubled::new(1., 1e-20),
+        Doubled::new(-2.5, -0.),
+        Doubled::new(3e300, -1e284),
+        Doubled::new(-0., 0.),
+    ];
+    let x = D4::from_array(lanes);
+    assert_eq!(x.to_array(), lanes);
+    assert_eq!(D4::from(lanes), x);
+    let back: [Doubled<f64>; 4] = x.into();
+    assert_eq!(back, lanes);
+    assert!(back[1].1.is_sign_negative() && back[3].0.is_sign_negative());
+    for i in 0..4 {
+        let v = Doubled::new(i as f64 + 0.5, -1e-25);
+        let y = x.replace(i, v);
+        for (j, &l) in lanes.iter().enumerate() {
+            assert_eq!(y.extract(j), if i == j { v } else { l });
+        }
+    }
+
+    let lanes: [Doubled<f32>; 8] =
+        core::array::from_fn(|i| Doubled::new(i as f32 - 3.5, (i as f32 - 3.5) * 1e-9));
+    let x = Doubled::<Simd<f32, 8>>::from_array(lanes);
+    assert_eq!(x.to_array(), lanes);
+    assert_eq!(Doubled::<Simd<f32, 8>>::from(lanes), x);
+    let back: [Doubled<f32>; 8] = x.into();
+    assert_eq!(back, lanes);
+    for i in 0..8 {
+        let v = Doubled::new(-100. * i as f32, 1e-7);
+        let y = x.replace(i, v);
+        for (j, &l) in lanes.iter().enumerate() {
+            assert_eq!(y.extract(j), if i == j { v } else { l });
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "index out of bounds")]
+fn replace_f64_lane_out_of_range() {
+    D4::splat(Doubled::new(1., 0.)).replace(4, Doubled::new(2., 0.));
+}
+
+#[test]
+#[should_panic(expected = "index out of bounds")]
+fn extract_f64_lane_out_of_range() {
+    D4::splat(Doubled::new(1., 0.)).extract(4);
+}
+
+#[test]
+#[should_panic(expected = "index out of bounds")]
+fn replace_f32_lane_out_of_range() {
+    Doubled::<Simd<f32, 8>>::splat(Doubled::new(1., 0.)).replace(8, Doubled::new(2., 0.));
+}
+
+#[test]
+#[should_panic(expected = "index out of bounds")]
+fn extract_f32_lane_out_of_range() {
+    Doubled::<Simd<f32, 8>>::splat(Doubled::new(1., 0.)).extract(8);
+}
+
+fn values(len: usize) -> Vec<Doubled<f64>> {
+    (0..len)
+        .map(|i| Doubled::new(i as f64 + 1., (i as f64 + 1.) * 1e-20))
+        .collect()
+}
+
+#[test]
+fn interleaved_loads_and_stores() {
+    let xs = values(6);
+    assert_eq!(
+        D4::load_interleaved(&xs[2..]).to_array(),
+        [xs[2], xs[3], xs[4], xs[5]]
+    );
+    let zero = Doubled::new(0., 0.);
+    assert_eq!(
+        D4::load_interleaved_or_default(&xs[4..]).to_array(),
+        [xs[4], xs[5], zero, zero]
+    );
+    assert_eq!(D4::load_interleaved_or_default(&[]).to_array(), [zero; 4]);
+
+    let v = D4::load_interleaved(&xs);
+    let mut out = vec![zero; 5];
+    v.store_interleaved(&mut out);
+    assert_eq!(out, [xs[0], xs[1], xs[2], xs[3], zero]);
+
+    // Disabled lanes and lanes past the end of the tail are left alone.
+    let mut out = vec![zero; 3];
+    v.store_interleaved_select(&mut out, Mask::from_array([true, false, true, true]));
+    assert_eq!(out, [xs[0], zero, xs[2]]);
+}
+
+#[test]
+#[should_panic(expected = "slice length must be at least the number of lanes")]
+fn load_interleaved_short() {
+    D4::load_interleaved(&values(3));
+}
+
+#[test]
+fn split_loads_and_stores() {
+    let xs = values(6);
+    let (hi, lo): (Vec<f64>, Vec<f64>) = xs.iter().map(|x| (x.0, x.1)).unzip();
+    assert_eq!(
+        D4::load_split(&hi[1..], &lo[1..]).to_array(),
+        [xs[1], xs[2], xs[3], xs[4]]
+    );
+    let zero = Doubled::new(0., 0.);
+    assert_eq!(
+        D4::load_split_or_default(&hi[5..], &lo[5..]).to_array(),
+        [xs[5], zero, zero, zero]
+    );
+
+    let v = D4::load_split(&hi, &lo);
+    let (mut h, mut l) = ([0.; 4], [0.; 4]);
+    v.store_split(&mut h, &mut l);
+    assert_eq!((h.as_slice(), l.as_slice()), (&hi[..4], &lo[..4]));
+    let (mut h, mut l) = ([0.; 2], [0.; 2]);
+    v.store_split_select(&mut h, &mut l, Mask::from_array([false, true, true, true]));
+    assert_eq!((h, l), ([0., hi[1]], [0., lo[1]]));
+}
+
+#[test]
+#[should_panic(expected = "high and low words must have the same length")]
+fn load_split_or_default_mismatched() {
+    D4::load_split_or_default(&[1., 2.], &[0.]);
+}
+
+#[test]
+fn gathers_and_scatters() {
+    let xs = values(6);
+    let (hi, lo): (Vec<f64>, Vec<f64>) = xs.iter().map(|x| (x.0, x.1)).unzip();
+    let zero = Doubled::new(0., 0.);
+    // A repeated index and one out of bounds.
+    let idxs = Simd::from_array([5, 0, 5, 6]);
+    let expected = [xs[5], xs[0], xs[5], zero];
+    assert_eq!(D4::gather_interleaved(&xs, idxs).to_array(), expected);
+    assert_eq!(D4::gather_split(&hi, &lo, idxs).to_array(), expected);
+
+    // For the repeated index the last lane wins, the out-of-bounds lane is
+    // skipped.
+    let v = D4::from_array([xs[0], xs[1], xs[2], xs[3]]);
+    let idxs = Simd::from_array([1, 3, 1, 100]);
+    let mut out = vec![zero; 4];
+    v.scatter_interleaved(&mut out, idxs);
+    assert_eq!(out, [zero, xs[2], zero, xs[1]]);
+    let (mut h, mut l) = ([0.; 4], [0.; 4]);
+    v.scatter_split(&mut h, &mut l, idxs);
+    assert_eq!((h, l), ([0., hi[2], 0., hi[1]], [0., lo[2], 0., lo[1]]));
+}
+
+#[test]
+#[should_panic(expected = "high and low words must have the same length")]
+fn gather_split_mismatched() {
+    D4::gather_split(&[1., 2., 3.], &[0., 0.], Simd::splat(2));
+}
+
+#[test]
+#[should_panic(expected = "high and low words must have the same length")]
+fn scatter_split_mismatched() {
+    D4::splat(Doubled::new(1., 0.)).scatter_split(&mut [0.; 3], &mut [0.; 2], Simd::splat(2));
+}
+
+#[test]
+fn reductions() {
+    let x = d4([(1., 1e-20), (2., -1e-20), (3., 1e-20), (4., 0.)]);
+    assert_eq!(x.reduce_sum(), Doubled::new(10., 1e-20));
+    let p = x.reduce_product();
+    assert_eq!(p.0, 24.);
+    assert!((p.1 - 24. * (1. - 0.5 + 1. / 3.) * 1e-20).abs() < 1e-35);
+
+    // Ties of the high words are broken by the low words.
+    let x = d4([(1., 1e-20), (1., -1e-20), (1., 0.), (-1., 1e-20)]);
+    assert_eq!(x.reduce_min(), Doubled::new(-1., 1e-20));
+    assert_eq!(x.reduce_max(), Doubled::new(1., 1e-20));
+    let x = d4([(1., 1e-20), (1., -1e-20), (1., 0.), (1., 2e-20)]);
+    assert_eq!(x.reduce_min(), Doubled::new(1., -1e-20));
+    assert_eq!(x.reduce_max(), Doubled::new(1., 2e-20));
+
+    // NaN lanes are ignored, in any position, unless all lanes are NaN.
+    let nan = (f64::NAN, 0.);
+    for lane in 0..4 {
+        let mut lanes = [(2., 0.), (-3., 0.), (5., 0.), (0.5, 0.)];
+        lanes[lane] = nan;
+        let x = d4(lanes);
+        let finite: Vec<f64> = lanes.iter().map(|l| l.0).filter(|v| !v.is_nan()).collect();
+        assert_eq!(
+            x.reduce_min().0,
+            finite.iter().copied().fold(f64::INFINITY, f64::min)
+        );
+        assert_eq!(
+            x.reduce_max().0,
+            finite.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+        );
+        assert!(x.reduce_sum().0.is_nan());
+    }
+    let x = d4([nan; 4]);
+    assert!(x.reduce_min().0.is_nan() && x.reduce_max().0.is_nan());
+
+    // A single lane is its own reduction.
+    let one = Doubled::<Simd<f64, 1>>::from_array([Doubled::new(-7., 1e-20)]);
+    for r in [
+        one.reduce_sum(),
+        one.reduce_product(),
+        one.reduce_min(),
+        one.reduce_max(),
+    ] {
+        assert_eq!(r, Doubled::new(-7., 1e-20));
+    }
+
+    let x = Doubled::<Simd<f32, 8>>::from_array(core::array::from_fn(|i| {
+        Doubled::new(1., if i == 5 { 1e-9 } else { 0. })
+    }));
+    assert_eq!(x.reduce_max(), Doubled::new(1., 1e-9));
+    assert_eq!(x.reduce_sum(), Doubled::new(8., 1e-9));
+}
@@ -0,0 +1,217 @@
+use doubled::{slice, AddChecked, Doubled, MulAsDoubled, Normalize};
+
+/// Deterministic values of mixed signs and magnitudes.
+fn values(len: usize, seed: u64) -> Vec<f64> {
+    let mut s = seed;
+    (0..len)
+        .map(|_| {
+            s = s
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let x = (s >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+            x * (1 << (s % 16)) as f64
+        })
+        .collect()
+}
+
+fn close(x: Doubled<f64>, e: Doubled<f64>, scale: f64) -> bool {
+    let d = x - e;
+    (d.0 + d.1).abs() <= 1e-28 * scale
+}
+
+fn doubled(len: usize, seed: u64) -> Vec<Doubled<f64>> {
+    values(len, seed)
+        .iter()
+        .zip(values(len, seed + 1))
+        .map(|(&hi, lo)| {
+            Doubled::new(hi, 0.)
+                .add_checked(lo * 1e-17 * hi.abs())
+                .normalize()
+        })
+        .collect()
+}
+
+#[test]
+fn elementwise_kernels_match_scalar() {
+    // Lengths below, at and past a vector, with a partial tail.
+    for len in [0, 1, 3, 4, 8, 13, 100] {
+        let xs = values(len, 5);
+        let ys = values(len, 6);
+
+        let e = xs
+            .iter()
+            .fold(Doubled::new(0., 0.), |s, &x| (s + x).normalize());
+        let scale: f64 = xs.iter().map(|x| x.abs()).sum();
+        assert!(close(slice::sum(&xs), e, scale), "{len}");
+
+        let e = xs
+            .iter()
+            .zip(&ys)
+            .fold(Doubled::new(0., 0.), |s, (&x, &y)| {
+                (s + x.mul_as_doubled(y)).normalize()
+            });
+        let scale: f64 = xs.iter().zip(&ys).map(|(x, y)| (x * y).abs()).sum();
+        assert!(close(slice::dot(&xs, &ys), e, scale), "{len}");
+
+        let coeffs = [1.5, -0.25, 3., 0.125, -2.];
+        let mut out = vec![Doubled::new(0., 0.); len];
+        slice::polyval(&coeffs, &xs, &mut out);
+        for (&x, &r) in xs.iter().zip(&out) {
+            let e = coeffs
+                .iter()
+                .rev()
+                .fold(Doubled::new(0., 0.), |p, &c| (p * x + c).normalize());
+            let scale = coeffs.iter().rev().fold(0., |p, c| p * x.abs() + c.abs());
+            assert!(close(r, e, scale), "{x} {r:?} {e:?}");
+        }
+
+        let (a, b) = (doubled(len, 7), doubled(len, 9));
+        let mut out = vec![Doubled::new(0., 0.); len];
+        slice::mul(&a, &b, &mut out);
+        for ((&a, &b), &r) in a.iter().zip(&b).zip(&out) {
+            assert!(close(r, a * b, (a.0 * b.0).abs()), "{a:?} {b:?}");
+        }
+        slice::div(&a, &b, &mut out);
+        for ((&a, &b), &r) in a.iter().zip(&b).zip(&out) {
+            assert!(close(r, a / b, (a.0 / b.0).abs()), "{a:?} {b:?}");
+        }
+    }
+
+    let xs = [1e8f32, 1., -1e8, 0.5, 0.25, 1e-3, -1e-3, 4., 2.];
+    let s = slice::sum(&xs);
+    assert_eq!(s.0 + s.1, 7.75);
+    let d = slice::dot(&xs, &[1f32; 9]);
+    assert_eq!(d, s);
+}
+
+fn naive(
+    a: &[Doubled<f64>],
+    b: &[Doubled<f64>],
+    m: usize,
+    k: usize,
+    n: usize,
+) -> Vec<Doubled<f64>> {
+    let mut out = vec![Doubled::new(0., 0.); m * n];
+    for i in 0..m {
+        for j in 0..n {
+            for p in 0..k {
+                out[i * n + j] = (out[i * n + j] + a[i * k + p] * b[p * n + j]).normalize();
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn gemm_matches_naive() {
+    // Odd sizes exercise the partial row tile and the scalar columns, and the
+    // inner dimension spans more than one block.
+    for (m, k, n) in [(7, 300, 11), (4, 3, 8), (1, 1, 1), (0, 5, 3)] {
+        let a = values(m * k, 1);
+        let b = values(k * n, 2);
+        let (ad, bd): (Vec<_>, Vec<_>) = (
+            a.iter().map(|&x| Doubled::from(x)).collect(),
+            b.iter().map(|&x| Doubled::from(x)).collect(),
+        );
+        let expected = naive(&ad, &bd, m, k, n);
+
+        let mut out = vec![Doubled::new(0., 0.); m * n];
+        slice::gemm(&a, &b, &mut out, m, k, n);
+        let mut out_doubled = vec![Doubled::new(0., 0.); m * n];
+        slice::gemm_doubled(&ad, &bd, &mut out_doubled, m, k, n);
+        for ((x, y), e) in out.iter().zip(&out_doubled).zip(&expected) {
+            for r in [x, y] {
+                let d = *r - *e;
+                assert!(
+                    (d.0 + d.1).abs() <= 1e-28 * e.0.abs().max(1.),
+                    "{r:?} {e:?}"
+                );
+            }
+        }
+    }
+
+    let a = [1.5f32, -2., 0.25, 3.];
+    let b = [2f32, 1., -1., 4.];
+    let mut out = [Doubled::new(0f32, 0.); 4];
+    slice::gemm(&a, &b, &mut out, 2, 2, 2);
+    assert_eq!(out.map(|x| x.0 + x.1), [5., -6.5, -2.5, 12.25]);
+}
+
+#[test]
+fn spmv_csr_matches_naive() {
+    // Rows shorter and longer than a vector, and an empty row.
+    let lengths = [3, 0, 9, 4, 20, 1];
+    let n = 25;
+    let mut row_ptr = vec![0];
+    let mut col_idx = Vec::new();
+    for (i, &len) in lengths.iter().enumerate() {
+        col_idx.extend((0..len).map(|j| (7 * j + 3 * i) % n));
+        row_ptr.push(col_idx.len());
+    }
+    let vals = values(col_idx.len(), 3);
+    let x = values(n, 4);
+
+    let mut y = vec![Doubled::new(0., 0.); lengths.len()];
+    slice::spmv_csr(&row_ptr, &col_idx, &vals, &x, &mut y);
+    let mut rounded = vec![0.; lengths.len()];
+    slice::spmv_csr_rounded(&row_ptr, &col_idx, &vals, &x, &mut rounded);
+    for (i, r) in row_ptr.windows(2).enumerate() {
+        let e = (r[0]..r[1]).fold(Doubled::new(0., 0.), |s, p| {
+            (s + Doubled::from(vals[p]) * Doubled::from(x[col_idx[p]])).normalize()
+        });
+        let d = y[i] - e;
+        assert!(
+            (d.0 + d.1).abs() <= 1e-28 * e.0.abs().max(1.),
+            "{:?} {e:?}",
+            y[i]
+        );
+        assert_eq!(rounded[i], y[i].0 + y[i].1);
+    }
+
+    // The cancellation that `f64` accumulation gets wrong.
+    let mut y = [0.; 1];
+    slice::spmv_csr_rounded(
+        &[0, 5],
+        &[0, 1, 2, 3, 4],
+        &[1e16, 1., -1e16, 1., 1.],
+        &[1.; 5],
+        &mut y,
+    );
+    assert_eq!(y, [3.]);
+}
+
+#[test]
+#[should_panic(expected = "row_ptr must be non-decreasing")]
+fn spmv_csr_non_monotone_row_ptr() {
+    let mut y = [Doubled::new(0., 0.); 3];
+    slice::spmv_csr(&[0, 3, 2, 4], &[0; 4], &[1.; 4], &[1.; 2], &mut y);
+}
+
+#[test]
+#[should_panic(expected = "column index out of bounds")]
+fn spmv_csr_column_out_of_range() {
+    // Caught in the gather of a full vector of entries.
+    let mut y = [Doubled::new(0., 0.); 1];
+    slice::spmv_csr(
+        &[0, 8],
+        &[0, 1, 2, 3, 4, 5, 6, 8],
+        &[1.; 8],
+        &[1.; 8],
+        &mut y,
+    );
+}
+
+#[test]
+#[should_panic(expected = "column index out of bounds")]
+fn spmv_csr_column_out_of_range_in_tail() {
+    let mut y = [0.; 2];
+    slice::spmv_csr_rounded(&[0, 1, 2], &[0, 3], &[1.; 2], &[1.; 3], &mut y);
+}
+
+#[test]
+#[should_panic(expected = "column index out of bounds")]
+fn spmv_csr_f32_column_out_of_range() {
+    let mut y = [Doubled::new(0f32, 0.); 1];
+    let col_idx: Vec<usize> = (0..9).rev().collect();
+    slice::spmv_csr(&[0, 9], &col_idx, &[1f32; 9], &[1f32; 8], &mut y);
+}
@@ -0,0 +1,210 @@
+#![feature(portable_simd)]
+
+use core::simd::Simd;
+use doubled::*;
+
+const F64: [f64; 8] = [
+    0.,
+    -0.,
+    f64::INFINITY,
+    f64::NEG_INFINITY,
+    f64::NAN,
+    1.,
+    -1.,
+    2.,
+];
+const F32: [f32; 8] = [
+    0.,
+    -0.,
+    f32::INFINITY,
+    f32::NEG_INFINITY,
+    f32::NAN,
+    1.,
+    -1.,
+    2.,
+];
+
+/// Equal including the sign of zero, or both NaN.
+fn same_f64(x: f64, y: f64) -> bool {
+    (x.is_nan() && y.is_nan()) || x.to_bits() == y.to_bits()
+}
+
+fn same_f32(x: f32, y: f32) -> bool {
+    (x.is_nan() && y.is_nan()) || x.to_bits() == y.to_bits()
+}
+
+#[test]
+fn f64_conversions() {
+    for x in F64 {
+        assert!(same_f64(Doubled::from(x).into(), x), "{x}");
+        assert!(same_f64(x.as_doubled().into(), x), "{x}");
+    }
+}
+
+#[test]
+fn f64_ldexp() {
+    for x in F64 {
+        let d = Doubled::<f64>::from(x);
+        assert!(same_f64(d.ldexp(3).into(), x * 8.), "{x}");
+        if x == 0. || !x.is_finite() {
+            assert!(same_f64(d.frexp().0.into(), x) && d.frexp().1 == 0, "{x}");
+        }
+    }
+    assert_eq!(Doubled::<f64>::from(0.).ilogb(), i32::MIN);
+    assert_eq!(Doubled::<f64>::from(f64::NAN).ilogb(), i32::MIN);
+    assert_eq!(Doubled::<f64>::from(f64::NEG_INFINITY).ilogb(), i32::MAX);
+}
+
+#[test]
+fn f64_recip() {
+    for x in F64 {
+        assert!(
+            same_f64(Doubled::<f64>::from(x).recip().into(), 1. / x),
+            "{x}"
+        );
+        assert!(same_f64(x.recip_as_doubled().into(), 1. / x), "{x}");
+    }
+}
+
+#[test]
+fn f64_div() {
+    for x in F64 {
+        for y in F64 {
+            let q = Doubled::<f64>::from(x) / Doubled::from(y);
+            assert!(same_f64(q.into(), x / y), "{x} / {y}");
+        }
+    }
+}
+
+#[test]
+fn f64_sqrt() {
+    for x in F64 {
+        let r = Doubled::<f64>::from(x).sqrt();
+        assert!(same_f64(r.into(), x.sqrt()), "{x}");
+        if x == 0. || x == f64::INFINITY {
+            // The root itself, with a zero low word of its sign.
+            assert!(same_f64(r.0, x) && same_f64(r.1, 0f64.copysign(x)), "{x}");
+        }
+    }
+}
+
+#[test]
+fn f32_conversions() {
+    for x in F32 {
+        assert!(same_f32(Doubled::from(x).into(), x), "{x}");
+        assert!(same_f32(x.as_doubled().into(), x), "{x}");
+        assert!(
+            same_f32(Doubled::<f32>::from_f64(x as f64).into(), x),
+            "{x}"
+        );
+        let d: Doubled<f32> = Doubled::<f64>::from(x as f64).into();
+        assert!(same_f32(d.into(), x), "{x}");
+        assert!(same_f64(Doubled::<f64>::from(d).into(), x as f64), "{x}");
+        assert!(same_f64(d.to_f64(), x as f64), "{x}");
+    }
+}
+
+#[test]
+fn f32_recip() {
+    for x in F32 {
+        assert!(
+            same_f32(Doubled::<f32>::from(x).recip().into(), 1. / x),
+            "{x}"
+        );
+        assert!(same_f32(x.recip_as_doubled().into(), 1. / x), "{x}");
+    }
+}
+
+#[test]
+fn f32_div() {
+    for x in F32 {
+        for y in F32 {
+            let q = Doubled::<f32>::from(x) / Doubled::from(y);
+            assert!(same_f32(q.into(), x / y), "{x} / {y}");
+        }
+    }
+}
+
+#[test]
+fn f32_sqrt() {
+    for x in F32 {
+        let r = Doubled::<f32>::from(x).sqrt();
+        assert!(same_f32(r.into(), x.sqrt()), "{x}");
+        if x == 0. || x == f32::INFINITY {
+            assert!(same_f32(r.0, x) && same_f32(r.1, 0f32.copysign(x)), "{x}");
+        }
+    }
+}
+
+#[test]
+fn f64x_recip_sqrt() {
+    let x = Simd::from_array(F64);
+    let r: [f64; 8] = Doubled::from(x).recip().into_array();
+    let a: [f64; 8] = x.recip_as_doubled().into_array();
+    let d = Doubled::from(x).sqrt();
+    let s: [f64; 8] = d.into_array();
+    for i in 0..8 {
+        if F64[i] == 0. || F64[i] == f64::INFINITY {
+            assert!(same_f64(d.1[i], 0f64.copysign(F64[i])), "{}", F64[i]);
+        }
+        assert!(same_f64(r[i], 1. / F64[i]), "{}", F64[i]);
+        assert!(same_f64(a[i], 1. / F64[i]), "{}", F64[i]);
+        assert!(same_f64(s[i], F64[i].sqrt()), "{}", F64[i]);
+    }
+}
+
+#[test]
+fn f64x_div() {
+    let x = Doubled::from(Simd::from_array(F64));
+    for y in F64 {
+        let q: [f64; 8] = (x / Doubled::from(Simd::splat(y))).into_array();
+        for i in 0..8 {
+            assert!(same_f64(q[i], F64[i] / y), "{} / {y}", F64[i]);
+        }
+    }
+}
+
+#[test]
+fn f32x_recip_sqrt() {
+    let x = Simd::from_array(F32);
+    let r: [f32; 8] = Doubled::from(x).recip().into_array();
+    let a: [f32; 8] = x.recip_as_doubled().into_array();
+    let d = Doubled::from(x).sqrt();
+    let s: [f32; 8] = d.into_array();
+    for i in 0..8 {
+        if F32[i] == 0. || F32[i] == f32::INFINITY {
+            assert!(same_f32(d.1[i], 0f32.copysign(F32[i])), "{}", F32[i]);
+        }
+        assert!(same_f32(r[i], 1. / F32[i]), "{}", F32[i]);
+        assert!(same_f32(a[i], 1. / F32[i]), "{}", F32[i]);
+        assert!(same_f32(s[i], F32[i].sqrt()), "{}", F32[i]);
+    }
+}
+
+#[test]
+fn f32x_div() {
+    let x = Doubled::from(Simd::from_array(F32));
+    for y in F32 {
+        let q: [f32; 8] = (x / Doubled::from(Simd::splat(y))).into_array();
+        for i in 0..8 {
+            assert!(same_f32(q[i], F32[i] / y), "{} / {y}", F32[i]);
+        }
+    }
+}
+
+/// Rounds each lane to the base type.
+trait IntoArray<T, const N: usize> {
+    fn into_array(self) -> [T; N];
+}
+
+impl<const N: usize> IntoArray<f64, N> for Doubled<Simd<f64, N>> {
+    fn into_array(self) -> [f64; N] {
+        Simd::from(self).to_array()
+    }
+}
+
+impl<const N: usize> IntoArray<f32, N> for Doubled<Simd<f32, N>> {
+    fn into_array(self) -> [f32; N] {
+        Simd::from(self).to_array()
+    }
+}
@@ -0,0 +1,133 @@
+#![feature(portable_simd)]
+
+mod common;
+
+use common::abs;
+use core::simd::Simd;
+use doubled::stats::{LaneStats, RunningStats};
+use doubled::{Doubled, Normalize};
+
+type D = Doubled<f64>;
+
+/// `1e9 + [1, 2, 3, 10]` repeated `reps` times: the deviations from the mean
+/// `1e9 + 4` are `-3, -2, -1, 6`, so `Σ δ² = 50 reps` and `Σ δ³ = 180 reps`.
+fn offset_data(reps: usize) -> Vec<f64> {
+    (0..reps)
+        .flat_map(|_| [1., 2., 3., 10.].map(|x| 1e9 + x))
+        .collect()
+}
+
+fn check(stats: &RunningStats, reps: usize, tolerance: f64) {
+    let n = 4. * reps as f64;
+    assert_eq!(stats.count(), 4 * reps as u64);
+    assert!(abs(stats.mean() - D::from(1e9 + 4.)) < 1e9 * tolerance);
+    let variance = (D::from(50. * reps as f64) / D::from(n - 1.)).normalize();
+    assert!(
+        abs(stats.variance() - variance) < 20. * tolerance,
+        "{:?}",
+        stats.variance()
+    );
+    assert!(abs(stats.population_variance() - D::from(12.5)) < 20. * tolerance);
+    // √n 180 reps / (50 reps)^(3/2) = 360 / (50 √50), independent of `reps`.
+    let skewness = (D::from(360.) / (D::from(50.) * D::from(50.).sqrt()).normalize()).normalize();
+    assert!(
+        abs(stats.skewness() - skewness) < tolerance,
+        "{:?}",
+        stats.skewness()
+    );
+    assert_eq!((stats.min(), stats.max()), (1e9 + 1., 1e9 + 10.));
+}
+
+#[test]
+fn large_offset() {
+    let data = offset_data(1000);
+    let mut stats = RunningStats::new();
+    for &x in &data {
+        stats.push(x);
+    }
+    check(&stats, 1000, 1e-24);
+
+    // The textbook one-pass formula in `f64` loses the variance to the offset.
+    let n = data.len() as f64;
+    let sum: f64 = data.iter().sum();
+    let sum2: f64 = data.iter().map(|x| x * x).sum();
+    let naive = (sum2 - sum * sum / n) / (n - 1.);
+    assert!((naive - 50. * 1000. / (n - 1.)).abs() > 1.);
+}
+
+#[test]
+fn merge_and_batch() {
+    let data = offset_data(999);
+    let sequential: RunningStats = data.iter().copied().collect();
+
+    // Uneven chunks, as from a parallel reduction, including an empty one.
+    let mut merged = RunningStats::new();
+    for chunk in [&data[..0], &data[..7], &data[7..1000], &data[1000..]] {
+        let mut part = RunningStats::new();
+        part.extend(chunk.iter().copied());
+        merged.merge(&part);
+    }
+    check(&merged, 999, 1e-24);
+
+    // Lengths that do and do not fill the lanes.
+    for len in [0, 3, 4, 13, data.len()] {
+        let mut batch = RunningStats::new();
+        batch.push_slice(&data[..len]);
+        let expected: RunningStats = data[..len].iter().copied().collect();
+        assert_eq!(batch.count(), expected.count());
+        if len > 1 {
+            assert!(abs(batch.mean() - expected.mean()) < 1e-20);
+            assert!(abs(batch.variance() - expected.variance()) < 1e-22);
+            assert_eq!((batch.min(), batch.max()), (expected.min(), expected.max()));
+        }
+    }
+    let mut batch = RunningStats::new();
+    batch.push_slice(&data);
+    check(&batch, 999, 1e-24);
+    assert!(abs(sequential.variance() - batch.variance()) < 1e-22);
+}
+
+/// Widths other than the one `push_slice` uses.
+fn lane_stats<const N: usize>(data: &[f64]) {
+    let mut lanes = LaneStats::<N>::default();
+    for chunk in data.chunks_exact(N) {
+        lanes.push(Simd::from_slice(chunk));
+    }
+    assert_eq!(lanes.count(), (data.len() / N) as u64);
+    for (i, lane) in lanes.lanes().iter().enumerate() {
+        let expected: RunningStats = data.iter().skip(i).step_by(N).copied().collect();
+        assert_eq!(lane.count(), expected.count());
+        assert!(abs(lane.mean() - expected.mean()) < 1e-20);
+        assert!(abs(lane.variance() - expected.variance()) < 1e-22);
+        assert_eq!((lane.min(), lane.max()), (expected.min(), expected.max()));
+    }
+    check(&lanes.reduce(), data.len() / 4, 1e-24);
+}
+
+#[test]
+fn lanes() {
+    let data = offset_data(1000);
+    lane_stats::<1>(&data);
+    lane_stats::<2>(&data);
+    lane_stats::<8>(&data);
+    assert_eq!(LaneStats::<8>::new().reduce(), RunningStats::new());
+}
+
+#[test]
+fn degenerate() {
+    let empty = RunningStats::default();
+    assert_eq!(empty.count(), 0);
+    assert!(empty.mean().0.is_nan() && empty.variance().0.is_nan());
+    assert!(empty.min().is_nan() && empty.max().is_nan());
+
+    let one: RunningStats = [5.].into_iter().collect();
+    assert_eq!(one.mean(), D::from(5.));
+    assert!(one.variance().0.is_nan());
+    assert_eq!(one.population_variance(), D::from(0.));
+    assert!(one.skewness().0.is_nan());
+
+    // NaN reaches the moments but not the extremes.
+    let nan: RunningStats = [1., f64::NAN, 3.].into_iter().collect();
+    assert!(nan.mean().0.is_nan());
+    assert_eq!((nan.min(), nan.max()), (1., 3.));
+}